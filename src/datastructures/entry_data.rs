@@ -1,13 +1,272 @@
-// use crate::Entry;
-// use crate::Tag;
-
-// pub struct EntryData {
-//     entry: Entry,
-//     tags: Vec<Tag>
-// }
-
-// impl EntryData {
-//     pub fn from_entry(conn: &mut sqlx::SqliteConnection, entry: Entry) -> Result<Self, crate::Error> {
-//         let tags = entry.get_tags(conn)
-//     }
-// }
+use std::collections::HashMap;
+
+use snafu::ResultExt;
+use sqlx::FromRow;
+
+use crate::Entry;
+use crate::Tag;
+use crate::TextField;
+use crate::models::boolean_field::BooleanField;
+use crate::models::datetime_field::DatetimeField;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// An entry with its tags and fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryData {
+    pub entry: Entry,
+    pub tags: Vec<EntryTag>,
+    pub text_fields: Vec<TextField>,
+    pub datetime_fields: Vec<DatetimeField>,
+    pub boolean_fields: Vec<BooleanField>,
+}
+
+/// A tag of an entry, with its direct parents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryTag {
+    pub tag: Tag,
+    pub parents: Vec<Tag>,
+}
+
+impl EntryData {
+    /// Load the data of a single entry
+    pub async fn from_entry(
+        conn: &mut sqlx::SqliteConnection,
+        entry: Entry,
+    ) -> Result<Self, SqlxError> {
+        let mut data = Self::from_entries(conn, vec![entry]).await?;
+        Ok(data.pop().expect("One entry was given"))
+    }
+
+    /// Load the data of multiple entries. This always takes the same number of queries, no matter the number of entries.
+    ///
+    /// The order of the entries is kept
+    pub async fn from_entries(
+        conn: &mut sqlx::SqliteConnection,
+        entries: Vec<Entry>,
+    ) -> Result<Vec<Self>, SqlxError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let entry_ids =
+            serde_json::to_string(&entries.iter().map(|entry| entry.id).collect::<Vec<_>>())
+                .expect("Ids are serializable");
+
+        let tags = fetch_entry_tags(conn, &entry_ids).await?;
+        let text_fields = group_by_entry(
+            fetch_fields::<TextField>(conn, "text_fields", &entry_ids).await?,
+            |field| field.entry_id,
+        );
+        let datetime_fields = group_by_entry(
+            fetch_fields::<DatetimeField>(conn, "datetime_fields", &entry_ids).await?,
+            |field| field.entry_id,
+        );
+        let boolean_fields = group_by_entry(
+            fetch_fields::<BooleanField>(conn, "boolean_fields", &entry_ids).await?,
+            |field| field.entry_id,
+        );
+
+        // The same entry may be given multiple times, so the data is cloned out of the maps
+        Ok(entries
+            .into_iter()
+            .map(|entry| Self {
+                tags: tags.get(&entry.id).cloned().unwrap_or_default(),
+                text_fields: text_fields.get(&entry.id).cloned().unwrap_or_default(),
+                datetime_fields: datetime_fields.get(&entry.id).cloned().unwrap_or_default(),
+                boolean_fields: boolean_fields.get(&entry.id).cloned().unwrap_or_default(),
+                entry,
+            })
+            .collect())
+    }
+}
+
+#[derive(FromRow)]
+struct TagEntryRow {
+    entry_id: i64,
+    #[sqlx(flatten)]
+    tag: Tag,
+}
+
+#[derive(FromRow)]
+struct TagParentRow {
+    child_id: i64,
+    #[sqlx(flatten)]
+    parent: Tag,
+}
+
+/// Fetch the tags of the entries, and their parents, in two queries
+async fn fetch_entry_tags(
+    conn: &mut sqlx::SqliteConnection,
+    entry_ids: &str,
+) -> Result<HashMap<i64, Vec<EntryTag>>, SqlxError> {
+    let rows: Vec<TagEntryRow> = sqlx::query_as(
+        "SELECT `tag_entries`.`entry_id`, `tags`.*
+        FROM `tag_entries`
+            INNER JOIN `tags` ON `tags`.`id` = `tag_entries`.`tag_id`
+        WHERE `tag_entries`.`entry_id` IN (SELECT value FROM JSON_EACH($1))
+        ORDER BY `tags`.`name`",
+    )
+    .bind(entry_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .context(SqlxSnafu)?;
+
+    let parent_rows: Vec<TagParentRow> = sqlx::query_as(
+        "SELECT `tag_parents`.`child_id`, `tags`.*
+        FROM `tag_parents`
+            INNER JOIN `tags` ON `tags`.`id` = `tag_parents`.`parent_id`
+        WHERE `tag_parents`.`child_id` IN (
+            SELECT `tag_entries`.`tag_id` FROM `tag_entries` WHERE `tag_entries`.`entry_id` IN (SELECT value FROM JSON_EACH($1))
+        )
+        ORDER BY `tags`.`name`",
+    )
+    .bind(entry_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .context(SqlxSnafu)?;
+
+    let mut parents: HashMap<i64, Vec<Tag>> = HashMap::new();
+    for row in parent_rows {
+        parents.entry(row.child_id).or_default().push(row.parent);
+    }
+
+    let mut tags: HashMap<i64, Vec<EntryTag>> = HashMap::new();
+    for row in rows {
+        tags.entry(row.entry_id).or_default().push(EntryTag {
+            parents: parents.get(&row.tag.id).cloned().unwrap_or_default(),
+            tag: row.tag,
+        });
+    }
+
+    Ok(tags)
+}
+
+async fn fetch_fields<T>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_ids: &str,
+) -> Result<Vec<T>, SqlxError>
+where
+    T: for<'r> FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    sqlx::query_as(sqlx::AssertSqlSafe(format!(
+        "SELECT * FROM `{table}` WHERE `entry_id` IN (SELECT value FROM JSON_EACH($1)) ORDER BY `position`, `id`"
+    )))
+    .bind(entry_ids)
+    .fetch_all(conn)
+    .await
+    .context(SqlxSnafu)
+}
+
+fn group_by_entry<T>(values: Vec<T>, entry_id: impl Fn(&T) -> i64) -> HashMap<i64, Vec<T>> {
+    let mut grouped: HashMap<i64, Vec<T>> = HashMap::new();
+    for value in values {
+        grouped.entry(entry_id(&value)).or_default().push(value);
+    }
+    grouped
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::Entry;
+    use crate::datastructures::entry_data::EntryData;
+    use crate::query::eq_any_entry_id::EqAnyEntryId;
+    use crate::query::trait_entry_filter::QueryEntryFilter as _;
+    use crate::tests::fixtures::data::get_test_library;
+
+    #[tokio::test]
+    pub async fn entry_data_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO text_fields (value, type_key, entry_id, position) VALUES
+                ('jane smith', 'AUTHOR', 2, 0),
+                ('Bob', 'AUTHOR', 3, 0),
+                (NULL, 'TITLE', 3, 1);
+            INSERT INTO datetime_fields (value, type_key, entry_id, position) VALUES
+                ('2021-02-03 12:00:00', 'DATE_TAKEN', 2, 1);
+            INSERT INTO value_type VALUES ('FAVORITE', 'Favorite', 'BOOLEAN', 0, 30);
+            INSERT INTO boolean_fields (value, type_key, entry_id, position) VALUES
+                (0, 'FAVORITE', 2, 2);",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        let mut entries = EqAnyEntryId(vec![3, 2, 5]).fetch_all(conn).await.unwrap();
+        let doge = Entry::find_by_id(conn, 2).await.unwrap().unwrap();
+        entries.push(doge);
+        let ids = entries.iter().map(|entry| entry.id).collect::<Vec<_>>();
+        let data = EntryData::from_entries(conn, entries).await.unwrap();
+
+        // The order is kept, and duplicated entries all get their data
+        assert_eq!(
+            data.iter().map(|data| data.entry.id).collect::<Vec<_>>(),
+            ids
+        );
+        let doges = data
+            .iter()
+            .filter(|data| data.entry.id == 2)
+            .collect::<Vec<_>>();
+        assert_eq!(doges.len(), 2);
+        assert_eq!(doges[0], doges[1]);
+
+        // Fields
+        let text_fields = doges[0]
+            .text_fields
+            .iter()
+            .map(|field| (field.type_key.as_str(), field.value.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(text_fields, vec![("AUTHOR", Some("jane smith"))]);
+
+        let datetime_fields = doges[0]
+            .datetime_fields
+            .iter()
+            .map(|field| (field.type_key.as_str(), field.value.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            datetime_fields,
+            vec![("DATE_TAKEN", Some("2021-02-03 12:00:00"))]
+        );
+
+        let boolean_fields = doges[0]
+            .boolean_fields
+            .iter()
+            .map(|field| (field.type_key.as_str(), field.value))
+            .collect::<Vec<_>>();
+        assert_eq!(boolean_fields, vec![("FAVORITE", false)]);
+
+        let doge_and_maxwell = data.iter().find(|data| data.entry.id == 3).unwrap();
+        let tags = doge_and_maxwell
+            .tags
+            .iter()
+            .map(|tag| tag.tag.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(tags, vec!["Doge", "Maxwell"]);
+
+        let maxwell_parents = doge_and_maxwell.tags[1]
+            .parents
+            .iter()
+            .map(|tag| tag.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(maxwell_parents, vec!["Cat", "Meme"]);
+
+        let doge_and_maxwell_texts = doge_and_maxwell
+            .text_fields
+            .iter()
+            .map(|field| (field.type_key.as_str(), field.value.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            doge_and_maxwell_texts,
+            vec![("AUTHOR", Some("Bob")), ("TITLE", None)]
+        );
+
+        let away = data.iter().find(|data| data.entry.id == 5).unwrap();
+        assert!(away.tags.is_empty());
+        assert!(away.text_fields.is_empty());
+        assert!(away.datetime_fields.is_empty());
+        assert!(away.boolean_fields.is_empty());
+    }
+}
@@ -12,6 +12,7 @@ pub use crate::models::errors::sqlx_error::SqlxError;
 
 // === Database ===
 pub use crate::models::entry::Entry;
+pub use crate::models::folder::Folder;
 pub use crate::models::library::Library;
 pub use crate::models::tag::Tag;
 pub use crate::models::tag_alias::TagAlias;
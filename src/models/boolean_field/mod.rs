@@ -0,0 +1,41 @@
+use sqlx::prelude::FromRow;
+
+use crate::models::entry::Entry;
+use crate::models::entry::relations::fields::find_fields;
+use crate::models::errors::sqlx_error::SqlxError;
+
+pub mod update;
+
+#[derive(Debug, FromRow, Clone, PartialEq, Eq, sequelles::Table)]
+#[sequelles(db_name = "boolean_fields", snafu)]
+#[sequelles(sqlite)]
+#[sequelles(update, insert_struct, select, delete)]
+#[sequelles(primary_key(key_name = "pk", columns(id)))]
+pub struct BooleanField {
+    #[sequelles(auto_increment)]
+    pub id: i64,
+
+    /// The key of the field's value type
+    pub type_key: String,
+    pub entry_id: i64,
+    pub value: bool,
+
+    /// The display order of the field among the fields of the entry
+    pub position: i64,
+}
+
+impl BooleanField {
+    pub async fn get_entry(&self, conn: &mut sqlx::SqliteConnection) -> Result<Entry, SqlxError> {
+        Entry::find_by_id(conn, self.entry_id)
+            .await
+            .transpose()
+            .expect("The boolean field has no associated entry")
+    }
+
+    pub async fn find_by_entry(
+        conn: &mut sqlx::SqliteConnection,
+        entry_id: i64,
+    ) -> Result<Vec<Self>, SqlxError> {
+        find_fields(conn, "boolean_fields", entry_id).await
+    }
+}
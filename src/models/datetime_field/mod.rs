@@ -1,16 +1,173 @@
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
 use sequelles::sqlx::FromRow;
+use snafu::ResultExt as _;
+use sqlx::AssertSqlSafe;
+
+use crate::models::entry::relations::fields::find_fields;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
 
 pub mod update;
 
+/// The format of the datetimes written by the app
+pub const DATETIME_FIELD_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The formats the app has written over time. `%.f` also accepts values without fractional seconds
+const LEGACY_DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// Parse a datetime field value. Dates without a time are set to midnight
+pub fn parse_datetime_value(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+
+    LEGACY_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(Default::default()))
+        })
+}
+
+/// Get the SQL expression normalizing the datetime field value in `column`. It is `NULL` if the value is invalid.
+///
+/// `datetime` alone also accepts `now`, julian day numbers, timezones or days past the end of the month,
+/// so the value must also be written in one of the formats accepted by [parse_datetime_value]
+pub(crate) fn datetime_value_sql(column: &str) -> String {
+    format!(
+        "(CASE WHEN trim({column}) GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]*'
+            AND trim({column}) NOT GLOB '*[^0-9 T:.-]*'
+            AND date(trim({column})) = substr(trim({column}), 1, 10)
+            THEN datetime(trim({column})) END)"
+    )
+}
+
+/// Format a datetime the way the app writes it
+pub fn format_datetime_value(value: &NaiveDateTime) -> String {
+    value.format(DATETIME_FIELD_FORMAT).to_string()
+}
+
 #[derive(Debug, FromRow, Clone, PartialEq, Eq, sequelles::Table)]
-#[sequelles(db_name = "datetime_field", snafu)]
+#[sequelles(db_name = "datetime_fields", snafu)]
 #[sequelles(sqlite)]
-#[sequelles(update, insert_struct, select)]
+#[sequelles(update, insert_struct, select, delete)]
 #[sequelles(primary_key(key_name = "pk", columns(id)))]
 pub struct DatetimeField {
     #[sequelles(auto_increment)]
     pub id: i64,
-    pub name: String,
+
+    /// The key of the field's value type (Ex: `DATE_TAKEN`)
+    pub type_key: String,
     pub entry_id: i64,
     pub value: Option<String>,
+
+    /// The display order of the field among the fields of the entry
+    pub position: i64,
+}
+
+impl DatetimeField {
+    pub async fn find_by_entry(
+        conn: &mut sqlx::SqliteConnection,
+        entry_id: i64,
+    ) -> Result<Vec<Self>, SqlxError> {
+        find_fields(conn, "datetime_fields", entry_id).await
+    }
+
+    /// The parsed value of the field. `None` if the field is empty, or its value can't be parsed
+    pub fn parsed_value(&self) -> Option<NaiveDateTime> {
+        self.value.as_deref().and_then(parse_datetime_value)
+    }
+
+    /// Get the fields holding a value that can't be parsed as a datetime.
+    ///
+    /// Those fields are ignored when sorting or filtering by date
+    pub async fn find_invalid(conn: &mut sqlx::SqliteConnection) -> Result<Vec<Self>, SqlxError> {
+        sqlx::query_as(AssertSqlSafe(format!(
+            "SELECT * FROM `datetime_fields` WHERE `value` IS NOT NULL AND {} IS NULL ORDER BY `entry_id`, `position`, `id`",
+            datetime_value_sql("`value`")
+        )))
+        .fetch_all(conn)
+        .await
+        .context(SqlxSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::models::datetime_field::DatetimeField;
+    use crate::models::datetime_field::format_datetime_value;
+    use crate::models::datetime_field::parse_datetime_value;
+    use crate::tests::fixtures::raw_library::get_empty_library;
+
+    #[test]
+    pub fn parse_datetime_value_test() {
+        let datetime = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(14, 5, 30)
+            .unwrap();
+
+        for value in [
+            "2024-03-09 14:05:30",
+            "2024-03-09T14:05:30",
+            " 2024-03-09 14:05:30 ",
+        ] {
+            assert_eq!(parse_datetime_value(value), Some(datetime), "{value}");
+        }
+
+        assert_eq!(
+            parse_datetime_value("2024-03-09T14:05:30.123456"),
+            Some(datetime + chrono::Duration::microseconds(123456))
+        );
+        assert_eq!(
+            parse_datetime_value("2024-03-09"),
+            NaiveDate::from_ymd_opt(2024, 3, 9)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(parse_datetime_value("09/03/2024"), None);
+        assert_eq!(format_datetime_value(&datetime), "2024-03-09 14:05:30");
+    }
+
+    #[tokio::test]
+    pub async fn find_invalid_test() {
+        let lib = get_empty_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        sqlx::raw_sql(
+            "INSERT INTO `datetime_fields` (`id`, `type_key`, `entry_id`, `value`, `position`) VALUES
+                (1, 'DATE_TAKEN', 1, '2024-03-09T14:05:30.5', 0),
+                (2, 'DATE_TAKEN', 1, 'Last summer', 1),
+                (3, 'DATE_TAKEN', 1, NULL, 2),
+                (4, 'DATE_TAKEN', 1, 'now', 3),
+                (5, 'DATE_TAKEN', 1, '2024', 4),
+                (6, 'DATE_TAKEN', 1, '2024-02-30', 5),
+                (7, 'DATE_TAKEN', 1, '2024-03-09 14:05:30+02:00', 6),
+                (8, 'DATE_TAKEN', 1, ' 2024-03-09 14:05 ', 7);",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+        // The values that SQLite's `datetime` accepts, but the app can't parse, are invalid too
+        let invalid = DatetimeField::find_invalid(conn).await.unwrap();
+        assert_eq!(
+            invalid.iter().map(|field| field.id).collect::<Vec<_>>(),
+            vec![2, 4, 5, 6, 7]
+        );
+        for field in DatetimeField::find_by_entry(conn, 1).await.unwrap() {
+            assert_eq!(
+                field.value.is_some() && field.parsed_value().is_none(),
+                invalid.contains(&field),
+                "{field:?}"
+            );
+        }
+    }
 }
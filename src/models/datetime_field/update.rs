@@ -32,6 +32,7 @@ impl DatetimeField {
         .await
         .context(SqlxSnafu)?;
 
+        trans.commit().await.context(SqlxSnafu)?;
         Ok(())
     }
 }
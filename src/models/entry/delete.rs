@@ -0,0 +1,283 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+use tracing::debug;
+
+use crate::Entry;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::query::trait_entry_filter::QueryEntryFilter;
+
+/// The tables that reference entries through an `entry_id` column
+pub(crate) const ENTRY_RELATION_TABLES: [&str; 4] = [
+    "tag_entries",
+    "text_fields",
+    "datetime_fields",
+    "boolean_fields",
+];
+
+/// What to do with the file of a deleted entry
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FileDeletion {
+    /// Leave the file on disk
+    #[default]
+    Keep,
+
+    /// Send the file to the trash
+    #[cfg(feature = "fs")]
+    Trash,
+
+    /// Delete the file permanently
+    Permanent,
+}
+
+impl Entry {
+    /// Delete the entry and all the rows referencing it.
+    ///
+    /// The file is only touched once the database changes are commited. Missing files are ignored
+    pub async fn delete_cascade(
+        self,
+        conn: &mut sqlx::SqliteConnection,
+        library_root: &Path,
+        file: FileDeletion,
+    ) -> Result<(), EntryDeleteError> {
+        Self::delete_cascade_all(conn, vec![self], library_root, file).await
+    }
+
+    /// Delete all the entries matching the filter, and all the rows referencing them.
+    ///
+    /// Returns the deleted entries
+    pub async fn delete_cascade_by_filter<F>(
+        conn: &mut sqlx::SqliteConnection,
+        filter: &F,
+        library_root: &Path,
+        file: FileDeletion,
+    ) -> Result<Vec<Self>, EntryDeleteError>
+    where
+        F: QueryEntryFilter + Sync,
+    {
+        let entries = filter.fetch_all(conn).await.context(SqlSnafu)?;
+        Self::delete_cascade_all(conn, entries.clone(), library_root, file).await?;
+        Ok(entries)
+    }
+
+    /// Delete the entries in a single transaction, then remove their files.
+    ///
+    /// The files that couldn't be removed are all reported in [EntryDeleteError::FilesError]
+    async fn delete_cascade_all(
+        conn: &mut sqlx::SqliteConnection,
+        entries: Vec<Self>,
+        library_root: &Path,
+        file: FileDeletion,
+    ) -> Result<(), EntryDeleteError> {
+        let folders = Folder::find_all_paths(conn).await.context(SqlSnafu)?;
+        let paths = entries
+            .iter()
+            .map(|entry| entry.get_full_path_in(&folders, library_root))
+            .collect::<Vec<_>>();
+
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        for entry in &entries {
+            debug!("Deleting entry `{}`", entry.path);
+            delete_entry_rows(&mut trans, entry.id)
+                .await
+                .context(SqlSnafu)?;
+        }
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        // The entries are already gone, so try to remove all the files
+        let errors = paths
+            .into_iter()
+            .filter_map(|path| remove_file(path, file).err())
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return FilesSnafu { errors }.fail();
+        }
+
+        Ok(())
+    }
+}
+
+async fn delete_entry_rows(
+    conn: &mut sqlx::SqliteConnection,
+    entry_id: i64,
+) -> Result<(), SqlxError> {
+    for table in ENTRY_RELATION_TABLES {
+        sqlx::query(AssertSqlSafe(format!(
+            "DELETE FROM `{table}` WHERE `entry_id` = $1"
+        )))
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+    }
+
+    sqlx::query("DELETE FROM `entries` WHERE `id` = $1")
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+    Ok(())
+}
+
+/// Remove the file of an entry. Missing files are ignored
+pub(crate) fn remove_file(path: PathBuf, file: FileDeletion) -> Result<(), EntryDeleteError> {
+    if file == FileDeletion::Keep || !path.exists() {
+        return Ok(());
+    }
+
+    match file {
+        FileDeletion::Keep => {}
+        #[cfg(feature = "fs")]
+        FileDeletion::Trash => trash::delete(&path).context(TrashSnafu { path })?,
+        FileDeletion::Permanent => std::fs::remove_file(&path).context(IoSnafu { path })?,
+    }
+
+    Ok(())
+}
+
+/// Error for [Entry::delete_cascade]
+#[derive(Debug, Snafu)]
+pub enum EntryDeleteError {
+    #[snafu(display("Couldn't delete `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: snafu::Backtrace,
+    },
+
+    #[snafu(display("Couldn't remove the files of {} deleted entries", errors.len()))]
+    FilesError { errors: Vec<EntryDeleteError> },
+
+    #[cfg(feature = "fs")]
+    #[snafu(display("Couldn't move `{}` to the trash", path.display()))]
+    Trash {
+        path: PathBuf,
+        source: trash::Error,
+        backtrace: snafu::Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::AssertSqlSafe;
+
+    use crate::Entry;
+    use crate::models::entry::delete::FileDeletion;
+    use crate::query::eq_entry_name::EqEntryName;
+    use crate::tests::fixtures::data::get_test_library_with_fields;
+
+    #[tokio::test]
+    pub async fn delete_cascade_test() {
+        let lib = get_test_library_with_fields().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        assert_eq!(count_tag_entries(conn).await, 5);
+
+        let maxwell = Entry::find_by_path(conn, "doge_and_maxwell.png")
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let maxwell_id = maxwell.id;
+        maxwell
+            .delete_cascade(conn, &lib.path, FileDeletion::Keep)
+            .await
+            .unwrap();
+        assert_eq!(count_tag_entries(conn).await, 3);
+
+        let deleted = Entry::delete_cascade_by_filter(
+            conn,
+            &EqEntryName("doge.png".to_string()),
+            &lib.path,
+            FileDeletion::Keep,
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(count_tag_entries(conn).await, 2);
+
+        // The fields of both entries are gone too
+        for table in ["text_fields", "datetime_fields", "boolean_fields"] {
+            let count: i64 = sqlx::query_scalar(AssertSqlSafe(format!(
+                "SELECT COUNT(*) FROM `{table}` WHERE `entry_id` IN ($1, $2)"
+            )))
+            .bind(maxwell_id)
+            .bind(deleted[0].id)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+            assert_eq!(count, 0, "{table}");
+        }
+        assert!(
+            Entry::find_by_path(conn, "doge.png")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    async fn count_tag_entries(conn: &mut sqlx::SqliteConnection) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM `tag_entries`")
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    pub async fn delete_cascade_file_test() {
+        use crate::Library;
+        use crate::models::entry::delete::EntryDeleteError;
+        use crate::query::entry_search_query::EntrySearchQuery;
+        use crate::query::trait_entry_filter::QueryEntryFilter as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        std::fs::write(dir.path().join("maxwell.png"), "").unwrap();
+        let entry = lib.refresh().await.unwrap().added.pop().unwrap();
+
+        {
+            let conn = &mut *lib.db.get().await.unwrap();
+            entry
+                .delete_cascade(conn, &lib.path, FileDeletion::Permanent)
+                .await
+                .unwrap();
+            assert!(!dir.path().join("maxwell.png").exists());
+        }
+
+        // A file that can't be removed doesn't stop the others
+        for name in ["doge.png", "oiia.png", "wow.png"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        lib.refresh().await.unwrap();
+        std::fs::remove_file(dir.path().join("oiia.png")).unwrap();
+        std::fs::create_dir(dir.path().join("oiia.png")).unwrap();
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        let filter = EntrySearchQuery::from(EqEntryName("doge.png".to_string()))
+            .or(EqEntryName("oiia.png".to_string()).into())
+            .or(EqEntryName("wow.png".to_string()).into());
+        let err =
+            Entry::delete_cascade_by_filter(conn, &filter, &lib.path, FileDeletion::Permanent)
+                .await
+                .unwrap_err();
+        assert!(matches!(err, EntryDeleteError::FilesError { ref errors } if errors.len() == 1));
+        assert!(!dir.path().join("doge.png").exists());
+        assert!(!dir.path().join("wow.png").exists());
+        assert!(filter.fetch_all(conn).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use snafu::Snafu;
+use snafu::ensure;
+
+use crate::Entry;
+use crate::models::library::Library;
+
+impl Entry {
+    /// Create a new entry from a file path. The path can be absolute, or relative to the library root.
+    ///
+    /// The path is stored with `/` separators, and the `filename` and `suffix` are derived the same way as the app does:
+    /// the suffix is the lowercased last extension, without the dot (`Photo.TAR.GZ` -> `gz`).
+    ///
+    /// Paths outside the library, or inside the `.TagStudio` folder are refused.
+    /// The entry isn't inserted, and has no dates
+    pub fn from_path(library: &Library, path: &Path) -> Result<Self, EntryFromPathError> {
+        let relative = if path.is_absolute() {
+            path.strip_prefix(&library.path)
+                .ok()
+                .filter(|_| !library.path.as_os_str().is_empty())
+                .ok_or_else(|| OutsideLibrarySnafu { path }.build())?
+        } else {
+            path
+        };
+
+        let mut components = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => components.extend(
+                    part.to_string_lossy()
+                        .split('\\')
+                        .filter(|part| !part.is_empty())
+                        .map(ToString::to_string),
+                ),
+                Component::CurDir => {}
+                _ => return OutsideLibrarySnafu { path }.fail(),
+            }
+        }
+
+        ensure!(!components.is_empty(), NotAFileSnafu { path });
+        ensure!(
+            components[0] != ".TagStudio",
+            InTagStudioFolderSnafu { path }
+        );
+
+        let filename = components.last().cloned().unwrap_or_default();
+        let suffix = Path::new(&filename)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        Ok(Self {
+            id: 0,
+            path: components.join("/"),
+            filename,
+            suffix,
+            date_created: None,
+            date_modified: None,
+            date_added: None,
+            folder_id: None,
+        })
+    }
+}
+
+/// Error for [Entry::from_path]
+#[derive(Debug, Snafu)]
+pub enum EntryFromPathError {
+    #[snafu(display("`{}` isn't in the library", path.display()))]
+    OutsideLibrary { path: PathBuf },
+
+    #[snafu(display("`{}` is inside the `.TagStudio` folder", path.display()))]
+    InTagStudioFolder { path: PathBuf },
+
+    #[snafu(display("`{}` doesn't point to a file", path.display()))]
+    NotAFile { path: PathBuf },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::models::entry::from_path::EntryFromPathError;
+
+    #[tokio::test]
+    pub async fn from_path_test() {
+        let mut lib = Library::in_memory().unwrap();
+        lib.path = "/library".into();
+
+        let entry = Entry::from_path(&lib, Path::new("/library/cats/Maxwell.Cat.PNG")).unwrap();
+        assert_eq!(entry.path, "cats/Maxwell.Cat.PNG");
+        assert_eq!(entry.filename, "Maxwell.Cat.PNG");
+        assert_eq!(entry.suffix, "png");
+
+        let entry = Entry::from_path(&lib, Path::new("./cats\\black/.hidden")).unwrap();
+        assert_eq!(entry.path, "cats/black/.hidden");
+        assert_eq!(entry.filename, ".hidden");
+        assert_eq!(entry.suffix, "");
+
+        assert!(matches!(
+            Entry::from_path(&lib, Path::new("/elsewhere/cat.png")),
+            Err(EntryFromPathError::OutsideLibrary { .. })
+        ));
+        assert!(matches!(
+            Entry::from_path(&lib, Path::new("cats/../../cat.png")),
+            Err(EntryFromPathError::OutsideLibrary { .. })
+        ));
+        assert!(matches!(
+            Entry::from_path(&lib, Path::new("/library/.TagStudio/ts_library.sqlite")),
+            Err(EntryFromPathError::InTagStudioFolder { .. })
+        ));
+        assert!(matches!(
+            Entry::from_path(&lib, Path::new("/library")),
+            Err(EntryFromPathError::NotAFile { .. })
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use std::backtrace::Backtrace;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use tracing::debug;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::models::library::Library;
+use crate::models::library::refresh::to_naive_local;
+use crate::query::trait_entry_filter::QueryEntryFilter;
+
+impl Entry {
+    /// Read the creation and modification dates of a file, in local time.
+    ///
+    /// The creation date is only available on filesystems that record it (`statx` on Linux)
+    pub fn read_fs_dates(
+        full_path: &Path,
+    ) -> Result<(Option<NaiveDateTime>, Option<NaiveDateTime>), std::io::Error> {
+        let metadata = std::fs::metadata(full_path)?;
+
+        Ok((
+            metadata.created().ok().map(to_naive_local),
+            metadata.modified().ok().map(to_naive_local),
+        ))
+    }
+
+    /// Update `date_created` and `date_modified` from the file's metadata. Returns true if they changed.
+    ///
+    /// Unlinked entries are left untouched
+    pub async fn sync_dates(
+        &mut self,
+        conn: &mut sqlx::SqliteConnection,
+        library_root: &Path,
+    ) -> Result<bool, SyncDatesError> {
+        let full_path = self
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(SqlSnafu)?;
+
+        self.sync_dates_from(conn, &full_path).await
+    }
+
+    /// Update the dates from the file at `full_path`, like [Entry::sync_dates]
+    async fn sync_dates_from(
+        &mut self,
+        conn: &mut sqlx::SqliteConnection,
+        full_path: &Path,
+    ) -> Result<bool, SyncDatesError> {
+        if !full_path.exists() {
+            return Ok(false);
+        }
+
+        let (date_created, date_modified) =
+            Self::read_fs_dates(full_path).context(IoSnafu { path: full_path })?;
+
+        // Keep the old creation date if the filesystem doesn't have one
+        let date_created = date_created.or(self.date_created);
+        if self.date_created == date_created && self.date_modified == date_modified {
+            return Ok(false);
+        }
+
+        debug!("Updating the dates of `{}`", self.path);
+        self.date_created = date_created;
+        self.date_modified = date_modified;
+        self.save(conn).await.context(SqlSnafu)?;
+
+        Ok(true)
+    }
+
+    /// Update the dates of all the entries matching the filter, in a single transaction.
+    ///
+    /// Returns the entries that changed
+    pub async fn sync_dates_by_filter<F>(
+        conn: &mut sqlx::SqliteConnection,
+        filter: &F,
+        library_root: &Path,
+    ) -> Result<Vec<Self>, SyncDatesError>
+    where
+        F: QueryEntryFilter + Sync,
+    {
+        let entries = filter.fetch_all(conn).await.context(SqlSnafu)?;
+        Self::sync_dates_all(conn, entries, library_root).await
+    }
+
+    async fn sync_dates_all(
+        conn: &mut sqlx::SqliteConnection,
+        entries: Vec<Self>,
+        library_root: &Path,
+    ) -> Result<Vec<Self>, SyncDatesError> {
+        let folders = Folder::find_all_paths(conn).await.context(SqlSnafu)?;
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        let mut changed = Vec::new();
+        for mut entry in entries {
+            let full_path = entry.get_full_path_in(&folders, library_root);
+            if entry.sync_dates_from(&mut trans, &full_path).await? {
+                changed.push(entry);
+            }
+        }
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        Ok(changed)
+    }
+}
+
+impl Library {
+    /// Update the dates of all the entries of the library from the filesystem.
+    ///
+    /// Returns the entries that changed
+    pub async fn sync_dates(&self) -> Result<Vec<Entry>, SyncDatesError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let entries = sqlx::query_as("SELECT * FROM `entries`")
+            .fetch_all(&mut *conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        Entry::sync_dates_all(conn, entries, &self.path).await
+    }
+}
+
+/// Error for [Entry::sync_dates]
+#[derive(Debug, Snafu)]
+pub enum SyncDatesError {
+    #[snafu(display("Couldn't read the metadata of `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::query::eq_entry_name::EqEntryName;
+
+    #[tokio::test]
+    pub async fn sync_dates_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let lib = Library::create(root.to_path_buf()).await.unwrap();
+
+        fs::write(root.join("maxwell.png"), "").unwrap();
+        fs::write(root.join("doge.png"), "").unwrap();
+        lib.refresh().await.unwrap();
+
+        // Clear the dates, like the entries created by older tools
+        let conn = &mut *lib.db.get().await.unwrap();
+        sqlx::query("UPDATE `entries` SET `date_created` = NULL, `date_modified` = NULL")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let changed =
+            Entry::sync_dates_by_filter(conn, &EqEntryName("maxwell.png".to_string()), &lib.path)
+                .await
+                .unwrap();
+        assert_eq!(changed.len(), 1);
+        assert!(changed[0].date_modified.is_some());
+
+        let doge = Entry::find_by_path(conn, "doge.png")
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert!(doge.date_modified.is_none());
+
+        // Only doge is left
+        let changed = lib.sync_dates().await.unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].path, "doge.png");
+        assert!(lib.sync_dates().await.unwrap().is_empty());
+    }
+}
@@ -1,4 +1,3 @@
-use std::backtrace::Backtrace;
 use std::path::Path;
 
 use sequelles::Delete;
@@ -9,39 +8,63 @@ use sqlx::Acquire;
 use crate::Entry;
 use crate::SqlxError;
 use crate::TextField;
+use crate::models::boolean_field::BooleanField;
 use crate::models::datetime_field::DatetimeField;
 use crate::models::entry::EntrySqlError;
+use crate::models::entry::delete::EntryDeleteError;
+use crate::models::entry::delete::FileDeletion;
+use crate::models::entry::delete::remove_file;
 use crate::models::errors::sqlx_error::SqlxSnafu;
 use crate::models::tag_entry::TagEntry;
 
 impl Entry {
-    /// Merge another entry into self
+    /// Merge another entry into self. The file of the other entry is sent to the trash.
+    ///
+    /// Use [Entry::plan_merge] to preview the changes
     pub async fn merge_entry(
         &self,
         conn: &mut sqlx::SqliteConnection,
         other: Self,
         library_root: &Path,
+    ) -> Result<(), MergeEntryError> {
+        self.merge_entry_with_deletion(conn, other, library_root, FileDeletion::Trash)
+            .await
+    }
+
+    /// Merge another entry into self, and remove the file of the other entry as told by `file`
+    pub async fn merge_entry_with_deletion(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        other: Self,
+        library_root: &Path,
+        file: FileDeletion,
     ) -> Result<(), MergeEntryError> {
         let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
 
-        TagEntry::replace_entry(&mut trans, self.id, other.id)
+        TagEntry::replace_entry(&mut trans, other.id, self.id)
+            .await
+            .context(SqlSnafu)?;
+        TextField::replace_entry(&mut trans, other.id, self.id)
             .await
             .context(SqlSnafu)?;
-        TextField::replace_entry(&mut trans, self.id, other.id)
+        DatetimeField::replace_entry(&mut trans, other.id, self.id)
             .await
             .context(SqlSnafu)?;
-        DatetimeField::replace_entry(&mut trans, self.id, other.id)
+        BooleanField::replace_entry(&mut trans, other.id, self.id)
             .await
             .context(SqlSnafu)?;
 
-        let other_path = other.get_full_path(library_root);
+        let other_path = other
+            .resolve_full_path(&mut trans, library_root)
+            .await
+            .context(SqlSnafu)?;
         other.delete(&mut trans).await.context(EntrySqlSnafu)?;
 
-        if other_path.exists() {
-            trash::delete(other_path).context(TrashSnafu)?;
-        }
-
         trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        // Only remove the file once the merge is saved
+        remove_file(other_path, file).context(RemoveFileSnafu)?;
+
         Ok(())
     }
 }
@@ -57,8 +80,48 @@ pub enum MergeEntryError {
         source: EntrySqlError,
     },
 
-    Trash {
-        source: trash::Error,
-        backtrace: Backtrace,
+    #[snafu(display("Couldn't remove the file of the merged entry"))]
+    RemoveFile {
+        #[snafu(backtrace)]
+        source: EntryDeleteError,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::models::entry::delete::FileDeletion;
+    use crate::models::folder::Folder;
+
+    #[tokio::test]
+    pub async fn merge_entry_in_folder_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        let other_root = tempfile::tempdir().unwrap();
+        fs::write(lib.path.join("maxwell.png"), "maxwell").unwrap();
+        fs::write(lib.path.join("copy.png"), "not a copy").unwrap();
+        fs::write(other_root.path().join("copy.png"), "maxwell").unwrap();
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        let other = Folder::insert_new(conn, other_root.path()).await.unwrap();
+        let main = Entry::from_path(&lib, &lib.path.join("maxwell.png"))
+            .unwrap()
+            .insert(conn)
+            .await
+            .unwrap();
+        let mut copy = Entry::from_path(&lib, &lib.path.join("copy.png")).unwrap();
+        copy.folder_id = Some(other.id);
+        let copy = copy.insert(conn).await.unwrap();
+
+        main.merge_entry_with_deletion(conn, copy, &lib.path, FileDeletion::Permanent)
+            .await
+            .unwrap();
+
+        // Only the file in the folder of the merged entry is removed
+        assert!(!other_root.path().join("copy.png").exists());
+        assert!(lib.path.join("copy.png").exists());
+    }
+}
@@ -17,8 +17,14 @@ impl Entry {
         other: Self,
         library_root: &Path,
     ) -> Result<(), MergeSameEntryError> {
-        let self_global_path = self.get_full_path(library_root);
-        let other_global_path = other.get_full_path(library_root);
+        let self_global_path = self
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(DatabaseSnafu)?;
+        let other_global_path = other
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(DatabaseSnafu)?;
 
         // Check if the files are the same
         if !self_global_path
@@ -1,14 +1,14 @@
 use std::fs::rename;
 use std::path::Path;
 
-use sequelles::table::update::UpdateSelf;
-
 use crate::models::entry::Entry;
 
+pub mod dates;
 pub mod merge_entry;
 pub mod merge_same_entry;
 pub mod move_entry;
 pub mod move_or_merge;
+pub mod plan;
 impl Entry {
     /// Move the underlying file of the entry somewhere else in the library
     ///
@@ -36,12 +36,8 @@ impl Entry {
             .strip_prefix(library_path)
             .map_err(|_| crate::Error::PathNotInFolder)?;
 
-        self.move_file_inner(
-            conn,
-            &new_relative_path.to_string_lossy(),
-            new_relative_path,
-        )
-        .await
+        self.move_file_inner(conn, &new_relative_path.to_string_lossy(), library_path)
+            .await
     }
 
     /// Move the underlying file of the entry somewhere else in the library.
@@ -53,20 +49,28 @@ impl Entry {
         new_lib_path: &str,
         library_root: &Path,
     ) -> Result<(), crate::Error> {
-        let prev_path = self.get_full_path(library_root);
+        let prev_path = self.resolve_full_path(conn, library_root).await?;
         let new_path = library_root.join(new_lib_path);
 
-        if self.exists_on_disk(library_root)? {
+        if prev_path.try_exists()? {
             rename(prev_path, new_path)?;
         }
 
         self.path = new_lib_path.to_string();
-        self.update_self(conn).await?;
+        self.save(conn).await?;
 
         Ok(())
     }
 
-    pub fn exists_on_disk(&self, library_root: &Path) -> Result<bool, crate::Error> {
-        Ok(self.get_full_path(library_root).try_exists()?)
+    /// Check if the file of the entry exists, looking in the folder of the entry
+    pub async fn exists_on_disk(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        library_root: &Path,
+    ) -> Result<bool, crate::Error> {
+        Ok(self
+            .resolve_full_path(conn, library_root)
+            .await?
+            .try_exists()?)
     }
 }
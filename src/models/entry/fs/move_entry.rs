@@ -3,7 +3,6 @@ use std::path::Path;
 use std::path::StripPrefixError;
 
 use filium::path::PathExt;
-use sequelles::table::update::UpdateSelf;
 use snafu::ResultExt;
 use sqlx::Acquire;
 
@@ -30,7 +29,10 @@ impl Entry {
             .context(SqlxSnafu)
             .context(DatabaseSnafu)?;
 
-        let prev_path = self.get_full_path(library_root);
+        let prev_path = self
+            .resolve_full_path(&mut trans, library_root)
+            .await
+            .context(DatabaseSnafu)?;
         let relative_new_path = new_path
             .strip_prefix(library_root)
             .context(FileNotInLibrarySnafu)?;
@@ -53,7 +55,7 @@ impl Entry {
 
         // We have moved! Now let's update `self` and commit the changes to the database
         self.path = relative_new_path.display().to_string();
-        self.update_self(&mut trans).await.context(EntrySqlSnafu)?;
+        self.save(&mut trans).await.context(DatabaseSnafu)?;
 
         trans
             .commit()
@@ -0,0 +1,549 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use filium::path::PathExt;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Entry;
+use crate::models::entry::delete::ENTRY_RELATION_TABLES;
+use crate::models::entry::delete::FileDeletion;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// A single change of a [Plan]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedOperation {
+    /// Rename a file on disk
+    MoveFile { from: PathBuf, to: PathBuf },
+
+    /// Change the path of an entry, along with its filename and suffix. The path is relative to the library root
+    UpdateEntryPath {
+        entry_id: i64,
+        from: String,
+        to: String,
+    },
+
+    /// Make the rows of a table that reference an entry point to another
+    RewriteRows {
+        table: &'static str,
+        from_entry: i64,
+        to_entry: i64,
+        count: i64,
+    },
+
+    /// Delete the rows of a table that reference an entry
+    DeleteRows {
+        table: &'static str,
+        entry_id: i64,
+        count: i64,
+    },
+
+    /// Delete an entry
+    DeleteEntry { entry_id: i64 },
+
+    /// Send a file to the trash, once the database changes are saved
+    TrashFile { path: PathBuf },
+
+    /// Permanently delete a file, once the database changes are saved
+    DeleteFile { path: PathBuf },
+}
+
+/// A list of operations that can be previewed, then executed as a batch
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub operations: Vec<PlannedOperation>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Add the operations of another plan after this one
+    pub fn extend(&mut self, other: Plan) {
+        self.operations.extend(other.operations);
+    }
+
+    /// Execute the plan.
+    ///
+    /// The database changes are done in a single transaction, and the files are moved before it gets commited.
+    /// If anything fails, the moved files are put back. The files are only trashed or deleted after the commit
+    pub async fn execute(&self, conn: &mut sqlx::SqliteConnection) -> Result<(), PlanExecuteError> {
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+        let mut moved_files: Vec<(&Path, &Path)> = Vec::new();
+
+        let result = self
+            .execute_in_transaction(&mut trans, &mut moved_files)
+            .await;
+        let result = match result {
+            Ok(()) => trans.commit().await.context(SqlxSnafu).context(SqlSnafu),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            for (from, to) in moved_files.into_iter().rev() {
+                if let Err(move_err) = std::fs::rename(to, from) {
+                    warn!(
+                        "Couldn't move back `{}` to `{}`: {move_err}",
+                        to.display(),
+                        from.display()
+                    );
+                }
+            }
+
+            return Err(err);
+        }
+
+        for operation in &self.operations {
+            match operation {
+                PlannedOperation::TrashFile { path } if path.exists() => {
+                    trash::delete(path).context(TrashSnafu { path })?
+                }
+                PlannedOperation::DeleteFile { path } if path.exists() => {
+                    std::fs::remove_file(path).context(IoSnafu { path })?
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn execute_in_transaction<'a>(
+        &'a self,
+        conn: &mut sqlx::SqliteConnection,
+        moved_files: &mut Vec<(&'a Path, &'a Path)>,
+    ) -> Result<(), PlanExecuteError> {
+        for operation in &self.operations {
+            debug!("Executing {operation:?}");
+
+            match operation {
+                PlannedOperation::MoveFile { from, to } => {
+                    if to.try_exists().context(IoSnafu { path: to })? {
+                        return DestinationOccupiedSnafu { path: to }.fail();
+                    }
+
+                    std::fs::rename(from, to).context(IoSnafu { path: from })?;
+                    moved_files.push((from, to));
+                }
+                PlannedOperation::UpdateEntryPath { entry_id, to, .. } => {
+                    let filename = to.rsplit('/').next().unwrap_or(to);
+                    let suffix = Path::new(filename)
+                        .extension()
+                        .map(|ext| ext.to_string_lossy().to_lowercase())
+                        .unwrap_or_default();
+                    sqlx::query(
+                        "UPDATE `entries` SET `path` = $1, `filename` = $2, `suffix` = $3 WHERE `id` = $4",
+                    )
+                    .bind(to)
+                    .bind(filename)
+                    .bind(suffix)
+                    .bind(entry_id)
+                    .execute(&mut *conn)
+                    .await
+                    .context(SqlxSnafu)
+                    .context(SqlSnafu)?;
+                }
+                PlannedOperation::RewriteRows {
+                    table,
+                    from_entry,
+                    to_entry,
+                    ..
+                } => {
+                    // Rows that would be duplicates are dropped
+                    sqlx::raw_sql(AssertSqlSafe(format!(
+                        "UPDATE OR IGNORE `{table}` SET `entry_id` = {to_entry} WHERE `entry_id` = {from_entry};
+                        DELETE FROM `{table}` WHERE `entry_id` = {from_entry};"
+                    )))
+                    .execute(&mut *conn)
+                    .await
+                    .context(SqlxSnafu)
+                    .context(SqlSnafu)?;
+                }
+                PlannedOperation::DeleteRows {
+                    table, entry_id, ..
+                } => {
+                    sqlx::query(AssertSqlSafe(format!(
+                        "DELETE FROM `{table}` WHERE `entry_id` = $1"
+                    )))
+                    .bind(entry_id)
+                    .execute(&mut *conn)
+                    .await
+                    .context(SqlxSnafu)
+                    .context(SqlSnafu)?;
+                }
+                PlannedOperation::DeleteEntry { entry_id } => {
+                    sqlx::query("DELETE FROM `entries` WHERE `id` = $1")
+                        .bind(entry_id)
+                        .execute(&mut *conn)
+                        .await
+                        .context(SqlxSnafu)
+                        .context(SqlSnafu)?;
+                }
+                PlannedOperation::TrashFile { .. } | PlannedOperation::DeleteFile { .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// Plan the move of the entry and its file. This takes in a full path.
+    ///
+    /// Fails if there's already an entry or a file at the destination
+    pub async fn plan_move(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        new_path: &Path,
+        library_root: &Path,
+    ) -> Result<Plan, PlanError> {
+        let relative_new_path = new_path
+            .strip_prefix(library_root)
+            .ok()
+            .and_then(|path| path.to_str())
+            .map(|path| path.replace('\\', "/"))
+            .ok_or_else(|| NotInLibrarySnafu { path: new_path }.build())?;
+
+        let mut other_entries = Self::find_by_path(conn, &relative_new_path)
+            .await
+            .context(SqlSnafu)?;
+        if let Some(other) = other_entries.pop() {
+            return EntryPresentSnafu { other }.fail();
+        }
+
+        if new_path.try_exists().context(IoSnafu { path: new_path })? {
+            return DestinationOccupiedSnafu { path: new_path }.fail();
+        }
+
+        let mut plan = Plan::default();
+        let current_path = self
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(SqlSnafu)?;
+        if current_path.try_exists().context(IoSnafu {
+            path: &current_path,
+        })? {
+            plan.operations.push(PlannedOperation::MoveFile {
+                from: current_path,
+                to: new_path.to_path_buf(),
+            });
+        }
+        plan.operations.push(PlannedOperation::UpdateEntryPath {
+            entry_id: self.id,
+            from: self.path.clone(),
+            to: relative_new_path,
+        });
+
+        Ok(plan)
+    }
+
+    /// Plan the merge of another entry into self. Same as [Entry::merge_entry]
+    pub async fn plan_merge(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        other: &Self,
+        library_root: &Path,
+    ) -> Result<Plan, PlanError> {
+        let mut plan = Plan::default();
+
+        for table in ENTRY_RELATION_TABLES {
+            let count = count_entry_rows(conn, table, other.id)
+                .await
+                .context(SqlSnafu)?;
+
+            if count != 0 {
+                plan.operations.push(PlannedOperation::RewriteRows {
+                    table,
+                    from_entry: other.id,
+                    to_entry: self.id,
+                    count,
+                });
+            }
+        }
+
+        plan.operations
+            .push(PlannedOperation::DeleteEntry { entry_id: other.id });
+
+        let other_path = other
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(SqlSnafu)?;
+        if other_path
+            .try_exists()
+            .context(IoSnafu { path: &other_path })?
+        {
+            plan.operations
+                .push(PlannedOperation::TrashFile { path: other_path });
+        }
+
+        Ok(plan)
+    }
+
+    /// Plan the move of the entry, but if the destination already has an entry of the same file, plan a merge. Same as [Entry::move_or_merge_same]
+    pub async fn plan_move_or_merge_same(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        new_path: &Path,
+        library_root: &Path,
+    ) -> Result<Plan, PlanError> {
+        match self.plan_move(conn, new_path, library_root).await {
+            Err(PlanError::EntryPresent { other }) => {
+                let self_path = self
+                    .resolve_full_path(conn, library_root)
+                    .await
+                    .context(SqlSnafu)?;
+                let other_path = other
+                    .resolve_full_path(conn, library_root)
+                    .await
+                    .context(SqlSnafu)?;
+                let same_file = self_path
+                    .same_file(&other_path)
+                    .context(IoSnafu { path: new_path })?;
+
+                if !same_file {
+                    return DifferentFilesSnafu { other }.fail();
+                }
+
+                other.plan_merge(conn, self, library_root).await
+            }
+            result => result,
+        }
+    }
+
+    /// Plan the deletion of the entry. Same as [Entry::delete_cascade]
+    pub async fn plan_delete(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        library_root: &Path,
+        file: FileDeletion,
+    ) -> Result<Plan, PlanError> {
+        let mut plan = Plan::default();
+
+        for table in ENTRY_RELATION_TABLES {
+            let count = count_entry_rows(conn, table, self.id)
+                .await
+                .context(SqlSnafu)?;
+
+            if count != 0 {
+                plan.operations.push(PlannedOperation::DeleteRows {
+                    table,
+                    entry_id: self.id,
+                    count,
+                });
+            }
+        }
+
+        plan.operations
+            .push(PlannedOperation::DeleteEntry { entry_id: self.id });
+
+        let path = self
+            .resolve_full_path(conn, library_root)
+            .await
+            .context(SqlSnafu)?;
+        if path.try_exists().context(IoSnafu { path: &path })? {
+            match file {
+                FileDeletion::Keep => {}
+                FileDeletion::Trash => plan.operations.push(PlannedOperation::TrashFile { path }),
+                FileDeletion::Permanent => {
+                    plan.operations.push(PlannedOperation::DeleteFile { path })
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+}
+
+async fn count_entry_rows(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_id: i64,
+) -> Result<i64, SqlxError> {
+    sqlx::query_scalar(AssertSqlSafe(format!(
+        "SELECT COUNT(*) FROM `{table}` WHERE `entry_id` = $1"
+    )))
+    .bind(entry_id)
+    .fetch_one(conn)
+    .await
+    .context(SqlxSnafu)
+}
+
+/// Error while planning an operation
+#[derive(Debug, Snafu)]
+pub enum PlanError {
+    /// There is already an entry at the target location
+    #[snafu(display("There's already an entry at the destination"))]
+    EntryPresent { other: Entry },
+
+    /// The entry at the target location isn't the same file
+    #[snafu(display("The entry at the destination has a different file"))]
+    DifferentFiles { other: Entry },
+
+    #[snafu(display("`{}` already exists", path.display()))]
+    DestinationOccupied { path: PathBuf },
+
+    #[snafu(display("`{}` isn't in the library", path.display()))]
+    NotInLibrary { path: PathBuf },
+
+    #[snafu(display("Couldn't read `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+/// Error for [Plan::execute]
+#[derive(Debug, Snafu)]
+pub enum PlanExecuteError {
+    #[snafu(display("`{}` already exists", path.display()))]
+    DestinationOccupied { path: PathBuf },
+
+    #[snafu(display("Couldn't move or delete `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Couldn't move `{}` to the trash", path.display()))]
+    Trash { path: PathBuf, source: trash::Error },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::entry::delete::FileDeletion;
+    use crate::models::entry::fs::plan::PlannedOperation;
+    use crate::models::folder::Folder;
+
+    #[tokio::test]
+    pub async fn plan_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = Library::create(root.clone()).await.unwrap();
+
+        fs::write(root.join("maxwell.png"), "").unwrap();
+        fs::write(root.join("doge.png"), "").unwrap();
+        lib.refresh().await.unwrap();
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        let maxwell = Entry::find_by_path(conn, "maxwell.png")
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let doge = Entry::find_by_path(conn, "doge.png")
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let tag = Tag::from("Cat").insert_tag(conn).await.unwrap();
+        maxwell.add_tag(conn, &tag).await.unwrap();
+
+        // Move preview
+        let plan = maxwell
+            .plan_move(conn, &root.join("cat.jpg"), &lib.path)
+            .await
+            .unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![
+                PlannedOperation::MoveFile {
+                    from: root.join("maxwell.png"),
+                    to: root.join("cat.jpg")
+                },
+                PlannedOperation::UpdateEntryPath {
+                    entry_id: maxwell.id,
+                    from: "maxwell.png".to_string(),
+                    to: "cat.jpg".to_string()
+                }
+            ]
+        );
+
+        // Nothing changed until executed
+        assert!(root.join("maxwell.png").exists());
+        plan.execute(conn).await.unwrap();
+        assert!(root.join("cat.jpg").exists());
+        let maxwell = Entry::find_by_id(conn, maxwell.id).await.unwrap().unwrap();
+        assert_eq!(maxwell.path, "cat.jpg");
+        assert_eq!(maxwell.filename, "cat.jpg");
+        assert_eq!(maxwell.suffix, "jpg");
+
+        // Merge preview
+        let plan = doge.plan_merge(conn, &maxwell, &lib.path).await.unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![
+                PlannedOperation::RewriteRows {
+                    table: "tag_entries",
+                    from_entry: maxwell.id,
+                    to_entry: doge.id,
+                    count: 1
+                },
+                PlannedOperation::DeleteEntry {
+                    entry_id: maxwell.id
+                },
+                PlannedOperation::TrashFile {
+                    path: root.join("cat.jpg")
+                },
+            ]
+        );
+
+        // Entries of other folders are planned inside their folder
+        let other_dir = tempfile::tempdir().unwrap();
+        let other_root = other_dir.path().canonicalize().unwrap();
+        fs::write(other_root.join("copy.png"), "").unwrap();
+        let folder = Folder::insert_new(conn, &other_root).await.unwrap();
+        let mut copy = Entry::from_path(&lib, &root.join("copy.png")).unwrap();
+        copy.folder_id = Some(folder.id);
+        let copy = copy.insert(conn).await.unwrap();
+
+        let plan = doge.plan_merge(conn, &copy, &lib.path).await.unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![
+                PlannedOperation::DeleteEntry { entry_id: copy.id },
+                PlannedOperation::TrashFile {
+                    path: other_root.join("copy.png")
+                },
+            ]
+        );
+
+        // Delete preview
+        let plan = doge
+            .plan_delete(conn, &lib.path, FileDeletion::Permanent)
+            .await
+            .unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![
+                PlannedOperation::DeleteEntry { entry_id: doge.id },
+                PlannedOperation::DeleteFile {
+                    path: root.join("doge.png")
+                },
+            ]
+        );
+        plan.execute(conn).await.unwrap();
+        assert!(!root.join("doge.png").exists());
+        assert!(Entry::find_by_id(conn, doge.id).await.unwrap().is_none());
+    }
+}
@@ -1,27 +1,54 @@
 use snafu::ResultExt as _;
+use sqlx::AssertSqlSafe;
 use tracing::debug;
 
 use crate::Entry;
 use crate::models::errors::sqlx_error::SqlxError;
 use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
+use crate::models::library::capabilities::placeholders;
 
 impl Entry {
+    /// Insert the entry in the database. The `id` of `self` is ignored.
+    ///
+    /// This uses the schema capabilities cached on the connection. See [SchemaCapabilities::get_cached]
     pub async fn insert(&self, conn: &mut sqlx::SqliteConnection) -> Result<Self, SqlxError> {
+        let schema = SchemaCapabilities::get_cached(conn).await?;
+        self.insert_with_schema(conn, &schema).await
+    }
+
+    /// Insert the entry in the database, only writing the columns the schema has. The `id` of `self` is ignored.
+    pub async fn insert_with_schema(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        schema: &SchemaCapabilities,
+    ) -> Result<Self, SqlxError> {
         debug!("Adding entry `{}`", self.path);
 
-        let sql;
-        sea_query::sqlx::sqlite::query_as!(
-            sql = "INSERT INTO `entries` VALUES (NULL,
-                {self.path},
-                {self.filename},
-                {self.suffix},
-                {self.date_created},
-                {self.date_modified},
-                {self.date_added}
-            ) RETURNING *;"
-        )
-        .fetch_one(conn)
-        .await
-        .context(SqlxSnafu)
+        let columns = schema.entry_insert_columns();
+        let mut values = placeholders(6);
+        if schema.entry_has_folder_id {
+            // Entries without a folder are put in the first folder of the library
+            values.push_str(", COALESCE($7, (SELECT MIN(`id`) FROM `folders`))");
+        }
+
+        let sql = format!(
+            "INSERT INTO `entries` ({}) VALUES ({values}) RETURNING *;",
+            columns.join(", ")
+        );
+
+        let mut query = sqlx::query_as(AssertSqlSafe(sql))
+            .bind(&self.path)
+            .bind(&self.filename)
+            .bind(&self.suffix)
+            .bind(self.date_created)
+            .bind(self.date_modified)
+            .bind(self.date_added);
+
+        if schema.entry_has_folder_id {
+            query = query.bind(self.folder_id);
+        }
+
+        query.fetch_one(conn).await.context(SqlxSnafu)
     }
 }
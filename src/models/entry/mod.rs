@@ -6,12 +6,15 @@ use std::path::PathBuf;
 use chrono::NaiveDateTime;
 use sqlx::FromRow;
 
+pub mod delete;
+pub mod from_path;
 #[cfg(feature = "fs")]
 pub mod fs;
 pub mod insert;
 pub mod relations;
 pub mod select;
 pub mod tags;
+pub mod update;
 
 #[derive(Debug, FromRow, Clone, PartialEq, Eq, sequelles::Table)]
 #[sequelles(db_name = "entries", snafu)]
@@ -28,10 +31,16 @@ pub struct Entry {
     pub date_created: Option<NaiveDateTime>,
     pub date_modified: Option<NaiveDateTime>,
     pub date_added: Option<NaiveDateTime>,
+
+    /// The root folder of the entry. Older libraries don't have folders, and read it as `None`
+    #[sqlx(default)]
+    pub folder_id: Option<i64>,
 }
 
 impl Entry {
-    /// Get the full path on the filesystem
+    /// Get the full path on the filesystem, assuming the entry is in the library root.
+    ///
+    /// For libraries with multiple folders, use [Entry::resolve_full_path]
     pub fn get_full_path(&self, library_root: &Path) -> PathBuf {
         library_root.join(&self.path)
     }
@@ -0,0 +1,14 @@
+use crate::Entry;
+use crate::SqlxError;
+use crate::models::entry::relations::fields::delete_field;
+
+impl Entry {
+    /// Remove a boolean field from the entry. Returns `false` if the field doesn't belong to the entry
+    pub async fn remove_boolean_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        field_id: i64,
+    ) -> Result<bool, SqlxError> {
+        delete_field(conn, "boolean_fields", self.id, field_id).await
+    }
+}
@@ -0,0 +1,16 @@
+use crate::Entry;
+use crate::models::boolean_field::BooleanField;
+use crate::models::entry::relations::fields::insert_field;
+use crate::models::value_type::ValueTypeError;
+
+impl Entry {
+    /// Add a boolean field to the entry, after its other fields
+    pub async fn add_boolean_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        type_key: &str,
+        value: bool,
+    ) -> Result<BooleanField, ValueTypeError> {
+        insert_field(conn, "boolean_fields", self.id, type_key, value).await
+    }
+}
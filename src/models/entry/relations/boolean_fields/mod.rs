@@ -0,0 +1,4 @@
+pub mod delete;
+pub mod insert;
+pub mod select;
+pub mod update;
@@ -0,0 +1,13 @@
+use crate::Entry;
+use crate::SqlxError;
+use crate::models::boolean_field::BooleanField;
+
+impl Entry {
+    /// Get the boolean fields of the entry, in display order
+    pub async fn get_boolean_fields(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Vec<BooleanField>, SqlxError> {
+        BooleanField::find_by_entry(conn, self.id).await
+    }
+}
@@ -0,0 +1,16 @@
+use crate::Entry;
+use crate::SqlxError;
+use crate::models::boolean_field::BooleanField;
+use crate::models::entry::relations::fields::update_field;
+
+impl Entry {
+    /// Set the value of a boolean field of the entry. Returns `None` if the field doesn't belong to the entry
+    pub async fn update_boolean_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        field_id: i64,
+        value: bool,
+    ) -> Result<Option<BooleanField>, SqlxError> {
+        update_field(conn, "boolean_fields", self.id, field_id, value).await
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+
+use crate::Entry;
+use crate::models::datetime_field::DatetimeField;
+use crate::models::datetime_field::format_datetime_value;
+use crate::models::entry::relations::fields::insert_field;
+use crate::models::value_type::ValueTypeError;
+
+impl Entry {
+    /// Add a datetime field to the entry, after its other fields
+    pub async fn add_datetime_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        type_key: &str,
+        value: Option<NaiveDateTime>,
+    ) -> Result<DatetimeField, ValueTypeError> {
+        insert_field(
+            conn,
+            "datetime_fields",
+            self.id,
+            type_key,
+            value.as_ref().map(format_datetime_value),
+        )
+        .await
+    }
+}
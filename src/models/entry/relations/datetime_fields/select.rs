@@ -0,0 +1,13 @@
+use crate::Entry;
+use crate::SqlxError;
+use crate::models::datetime_field::DatetimeField;
+
+impl Entry {
+    /// Get the datetime fields of the entry, in display order
+    pub async fn get_datetime_fields(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Vec<DatetimeField>, SqlxError> {
+        DatetimeField::find_by_entry(conn, self.id).await
+    }
+}
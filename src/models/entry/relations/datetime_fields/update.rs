@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+
+use crate::Entry;
+use crate::SqlxError;
+use crate::models::datetime_field::DatetimeField;
+use crate::models::datetime_field::format_datetime_value;
+use crate::models::entry::relations::fields::update_field;
+
+impl Entry {
+    /// Set the value of a datetime field of the entry. Returns `None` if the field doesn't belong to the entry
+    pub async fn update_datetime_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        field_id: i64,
+        value: Option<NaiveDateTime>,
+    ) -> Result<Option<DatetimeField>, SqlxError> {
+        update_field(
+            conn,
+            "datetime_fields",
+            self.id,
+            field_id,
+            value.as_ref().map(format_datetime_value),
+        )
+        .await
+    }
+}
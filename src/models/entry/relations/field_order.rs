@@ -0,0 +1,240 @@
+use snafu::ResultExt;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+
+use crate::Entry;
+use crate::TextField;
+use crate::models::boolean_field::BooleanField;
+use crate::models::datetime_field::DatetimeField;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// Any field of an entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryField {
+    Text(TextField),
+    Datetime(DatetimeField),
+    Boolean(BooleanField),
+}
+
+impl EntryField {
+    pub fn field_id(&self) -> FieldId {
+        match self {
+            Self::Text(field) => FieldId::Text(field.id),
+            Self::Datetime(field) => FieldId::Datetime(field.id),
+            Self::Boolean(field) => FieldId::Boolean(field.id),
+        }
+    }
+
+    pub fn type_key(&self) -> &str {
+        match self {
+            Self::Text(field) => &field.type_key,
+            Self::Datetime(field) => &field.type_key,
+            Self::Boolean(field) => &field.type_key,
+        }
+    }
+
+    pub fn position(&self) -> i64 {
+        match self {
+            Self::Text(field) => field.position,
+            Self::Datetime(field) => field.position,
+            Self::Boolean(field) => field.position,
+        }
+    }
+}
+
+/// The id of a field. Each kind of field has its own table, so the ids are only unique within a kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldId {
+    Text(i64),
+    Datetime(i64),
+    Boolean(i64),
+}
+
+impl FieldId {
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "text_fields",
+            Self::Datetime(_) => "datetime_fields",
+            Self::Boolean(_) => "boolean_fields",
+        }
+    }
+
+    fn id(&self) -> i64 {
+        match self {
+            Self::Text(id) | Self::Datetime(id) | Self::Boolean(id) => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldMove {
+    Up,
+    Down,
+}
+
+impl Entry {
+    /// Get all the fields of the entry, in display order
+    pub async fn get_fields(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Vec<EntryField>, SqlxError> {
+        let mut fields = Vec::new();
+        fields.extend(
+            self.get_text_fields(conn)
+                .await?
+                .into_iter()
+                .map(EntryField::Text),
+        );
+        fields.extend(
+            self.get_datetime_fields(conn)
+                .await?
+                .into_iter()
+                .map(EntryField::Datetime),
+        );
+        fields.extend(
+            self.get_boolean_fields(conn)
+                .await?
+                .into_iter()
+                .map(EntryField::Boolean),
+        );
+
+        // Stable sort, so fields sharing a position stay grouped by kind
+        fields.sort_by_key(|field| field.position());
+        Ok(fields)
+    }
+
+    /// Move a field one place up or down in the display order. Returns `false` if the field couldn't move
+    pub async fn move_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        field: FieldId,
+        direction: FieldMove,
+    ) -> Result<bool, SqlxError> {
+        // The order is read in the transaction, so a concurrent change can't be overwritten
+        let mut trans = conn.begin().await.context(SqlxSnafu)?;
+        let mut order = self.get_field_order(&mut trans).await?;
+        let Some(index) = order.iter().position(|id| *id == field) else {
+            return Ok(false);
+        };
+
+        let other = match direction {
+            FieldMove::Up if index > 0 => index - 1,
+            FieldMove::Down if index + 1 < order.len() => index + 1,
+            _ => return Ok(false),
+        };
+
+        order.swap(index, other);
+        self.write_field_order(&mut trans, &order).await?;
+        trans.commit().await.context(SqlxSnafu)?;
+        Ok(true)
+    }
+
+    /// Set the display order of the fields of the entry.
+    ///
+    /// The fields missing from `order` are kept after the given ones, in their current order.
+    /// Fields of other entries are ignored
+    pub async fn set_field_order(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        order: &[FieldId],
+    ) -> Result<(), SqlxError> {
+        let mut trans = conn.begin().await.context(SqlxSnafu)?;
+        let current = self.get_field_order(&mut trans).await?;
+
+        let mut new_order: Vec<FieldId> = Vec::with_capacity(current.len());
+        for field in order {
+            if current.contains(field) && !new_order.contains(field) {
+                new_order.push(*field);
+            }
+        }
+        for field in current {
+            if !new_order.contains(&field) {
+                new_order.push(field);
+            }
+        }
+
+        self.write_field_order(&mut trans, &new_order).await?;
+        trans.commit().await.context(SqlxSnafu)
+    }
+
+    /// Get the ids of the fields, in display order
+    async fn get_field_order(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Vec<FieldId>, SqlxError> {
+        Ok(self
+            .get_fields(conn)
+            .await?
+            .iter()
+            .map(EntryField::field_id)
+            .collect())
+    }
+
+    /// Give each field its index in `order` as position. It should run in the transaction that read `order`
+    async fn write_field_order(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        order: &[FieldId],
+    ) -> Result<(), SqlxError> {
+        for (position, field) in order.iter().enumerate() {
+            sqlx::query(AssertSqlSafe(format!(
+                "UPDATE `{}` SET `position` = $1 WHERE `id` = $2 AND `entry_id` = $3",
+                field.table()
+            )))
+            .bind(position as i64)
+            .bind(field.id())
+            .bind(self.id)
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Entry;
+    use crate::models::entry::relations::field_order::EntryField;
+    use crate::models::entry::relations::field_order::FieldId;
+    use crate::models::entry::relations::field_order::FieldMove;
+    use crate::tests::fixtures::data::get_test_library;
+
+    #[tokio::test]
+    pub async fn field_order_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+        let entry = Entry::find_by_id(conn, 1).await.unwrap().unwrap();
+
+        let title = entry.add_text_field(conn, "TITLE", None).await.unwrap();
+        let date = entry.add_datetime_field(conn, "DATE", None).await.unwrap();
+        let url = entry.add_text_field(conn, "URL", None).await.unwrap();
+        assert_eq!((title.position, date.position, url.position), (0, 1, 2));
+
+        let (title, date, url) = (
+            FieldId::Text(title.id),
+            FieldId::Datetime(date.id),
+            FieldId::Text(url.id),
+        );
+        assert_eq!(order(&entry, conn).await, vec![title, date, url]);
+
+        assert!(entry.move_field(conn, url, FieldMove::Up).await.unwrap());
+        assert_eq!(order(&entry, conn).await, vec![title, url, date]);
+        assert!(!entry.move_field(conn, title, FieldMove::Up).await.unwrap());
+
+        entry.set_field_order(conn, &[date]).await.unwrap();
+        assert_eq!(order(&entry, conn).await, vec![date, title, url]);
+    }
+
+    async fn order(entry: &Entry, conn: &mut sqlx::SqliteConnection) -> Vec<FieldId> {
+        entry
+            .get_fields(conn)
+            .await
+            .unwrap()
+            .iter()
+            .map(EntryField::field_id)
+            .collect()
+    }
+}
@@ -0,0 +1,203 @@
+use snafu::ResultExt;
+use sqlx::AssertSqlSafe;
+use sqlx::Encode;
+use sqlx::FromRow;
+use sqlx::Sqlite;
+use sqlx::Type;
+use sqlx::sqlite::SqliteRow;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::value_type::SqlSnafu;
+use crate::models::value_type::ValueType;
+use crate::models::value_type::ValueTypeError;
+
+/// The tables storing the fields of the entries
+const FIELD_TABLES: [&str; 3] = ["text_fields", "datetime_fields", "boolean_fields"];
+
+/// Get a select of the positions of all the fields of an entry, using `${bind_id}` as the entry id
+fn entry_field_positions_sql(bind_id: u64) -> String {
+    FIELD_TABLES
+        .iter()
+        .map(|table| format!("SELECT `position` FROM `{table}` WHERE `entry_id` = ${bind_id}"))
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ")
+}
+
+/// Insert a field after all the other fields of the entry. The field type must exist, and be of the kind stored in `table`
+pub(crate) async fn insert_field<T, V>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_id: i64,
+    type_key: &str,
+    value: V,
+) -> Result<T, ValueTypeError>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    V: for<'q> Encode<'q, Sqlite> + Type<Sqlite> + Send,
+{
+    ValueType::check_field_table(conn, type_key, table).await?;
+
+    let positions = entry_field_positions_sql(3);
+    sqlx::query_as(AssertSqlSafe(format!(
+        "INSERT INTO `{table}` (`value`, `type_key`, `entry_id`, `position`)
+        VALUES ($1, $2, $3, (SELECT COALESCE(MAX(`position`) + 1, 0) FROM ({positions})))
+        RETURNING *"
+    )))
+    .bind(value)
+    .bind(type_key)
+    .bind(entry_id)
+    .fetch_one(conn)
+    .await
+    .context(SqlxSnafu)
+    .context(SqlSnafu)
+}
+
+/// Get the fields of an entry, in display order
+pub(crate) async fn find_fields<T>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_id: i64,
+) -> Result<Vec<T>, SqlxError>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    sqlx::query_as(AssertSqlSafe(format!(
+        "SELECT * FROM `{table}` WHERE `entry_id` = $1 ORDER BY `position`, `id`"
+    )))
+    .bind(entry_id)
+    .fetch_all(conn)
+    .await
+    .context(SqlxSnafu)
+}
+
+/// Set the value of a field of the entry. Returns `None` if the entry has no field with this id
+pub(crate) async fn update_field<T, V>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_id: i64,
+    field_id: i64,
+    value: V,
+) -> Result<Option<T>, SqlxError>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    V: for<'q> Encode<'q, Sqlite> + Type<Sqlite> + Send,
+{
+    sqlx::query_as(AssertSqlSafe(format!(
+        "UPDATE `{table}` SET `value` = $1 WHERE `id` = $2 AND `entry_id` = $3 RETURNING *"
+    )))
+    .bind(value)
+    .bind(field_id)
+    .bind(entry_id)
+    .fetch_optional(conn)
+    .await
+    .context(SqlxSnafu)
+}
+
+/// Remove a field of the entry. Returns `false` if the entry has no field with this id
+pub(crate) async fn delete_field(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    entry_id: i64,
+    field_id: i64,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query(AssertSqlSafe(format!(
+        "DELETE FROM `{table}` WHERE `id` = $1 AND `entry_id` = $2"
+    )))
+    .bind(field_id)
+    .bind(entry_id)
+    .execute(conn)
+    .await
+    .context(SqlxSnafu)?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::Entry;
+    use crate::models::value_type::FieldKind;
+    use crate::models::value_type::ValueType;
+    use crate::models::value_type::ValueTypeError;
+    use crate::tests::fixtures::data::get_test_library;
+
+    #[tokio::test]
+    pub async fn entry_fields_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+        let maxwell = Entry::find_by_id(conn, 1).await.unwrap().unwrap();
+        let doge = Entry::find_by_id(conn, 2).await.unwrap().unwrap();
+
+        // Text
+        let title = maxwell
+            .add_text_field(conn, "TITLE", Some("Maxwell"))
+            .await
+            .unwrap();
+        let url = maxwell.add_text_field(conn, "URL", None).await.unwrap();
+        assert_eq!((title.position, url.position), (0, 1));
+
+        let url = maxwell
+            .update_text_field(conn, url.id, Some("https://example.com"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(url.value.as_deref(), Some("https://example.com"));
+        assert_eq!(
+            maxwell.get_text_fields(conn).await.unwrap(),
+            vec![title.clone(), url]
+        );
+
+        // Fields of other entries can't be touched
+        assert!(
+            doge.update_text_field(conn, title.id, None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(!doge.remove_text_field(conn, title.id).await.unwrap());
+        assert!(maxwell.remove_text_field(conn, title.id).await.unwrap());
+        assert_eq!(maxwell.get_text_fields(conn).await.unwrap().len(), 1);
+
+        // The type of the field is checked
+        assert!(matches!(
+            maxwell.add_text_field(conn, "DATE_TAKEN", None).await,
+            Err(ValueTypeError::WrongKind { .. })
+        ));
+
+        // Datetime
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+        let date_taken = maxwell
+            .add_datetime_field(conn, "DATE_TAKEN", Some(date))
+            .await
+            .unwrap();
+        assert_eq!(date_taken.value.as_deref(), Some("2025-01-02 03:04:05"));
+        assert_eq!(
+            maxwell.get_datetime_fields(conn).await.unwrap(),
+            vec![date_taken]
+        );
+
+        // Boolean
+        ValueType::create_custom(conn, "Is Cat", FieldKind::Boolean)
+            .await
+            .unwrap();
+        let boolean = maxwell
+            .add_boolean_field(conn, "IS_CAT", false)
+            .await
+            .unwrap();
+        let boolean = maxwell
+            .update_boolean_field(conn, boolean.id, true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(boolean.value);
+        assert_eq!(
+            maxwell.get_boolean_fields(conn).await.unwrap(),
+            vec![boolean]
+        );
+    }
+}
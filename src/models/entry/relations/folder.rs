@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::Entry;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::folder::Folder;
+
+impl Entry {
+    /// Get the root folder of the entry. Returns `None` on libraries without folders
+    pub async fn get_folder(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Option<Folder>, SqlxError> {
+        match self.folder_id {
+            Some(folder_id) => Folder::find_by_id(conn, folder_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Get the full path on the filesystem, using the root folder of the entry.
+    ///
+    /// Entries without folder fallback on `library_root`
+    pub async fn resolve_full_path(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        library_root: &Path,
+    ) -> Result<PathBuf, SqlxError> {
+        Ok(match self.get_folder(conn).await? {
+            Some(folder) => folder.get_entry_full_path(self),
+            None => self.get_full_path(library_root),
+        })
+    }
+
+    /// Get the full path on the filesystem, using the paths from [Folder::find_all_paths].
+    ///
+    /// Entries without folder fallback on `library_root`
+    pub fn get_full_path_in(
+        &self,
+        folders: &HashMap<i64, PathBuf>,
+        library_root: &Path,
+    ) -> PathBuf {
+        match self.folder_id.and_then(|id| folders.get(&id)) {
+            Some(folder) => folder.join(&self.path),
+            None => self.get_full_path(library_root),
+        }
+    }
+
+    /// Get the entries of a full path, looking in the folder that contains it
+    pub async fn find_by_folder_full_path(
+        conn: &mut sqlx::SqliteConnection,
+        full_path: &Path,
+    ) -> Result<Vec<Self>, SqlxError> {
+        let Some(folder) = Folder::find_containing(conn, full_path).await? else {
+            return Ok(Vec::new());
+        };
+        let Some(relative_path) = folder.get_relative_path(full_path) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(Self::find_by_path(conn, &relative_path.to_string_lossy())
+            .await?
+            .into_iter()
+            .filter(|entry| entry.folder_id == Some(folder.id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sequelles::Delete as _;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::models::folder::Folder;
+    use crate::models::folder::delete::FolderDeleteError;
+
+    #[tokio::test]
+    pub async fn multiple_folders_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        let other_root = tempfile::tempdir().unwrap();
+        let other = Folder::insert_new(conn, other_root.path()).await.unwrap();
+        assert_eq!(Folder::find_all(conn).await.unwrap().len(), 2);
+
+        // Paths are unique in the whole library, even across folders
+        let mut entry = Entry {
+            id: 0,
+            path: "cats/cat.png".to_string(),
+            filename: "cat.png".to_string(),
+            suffix: "png".to_string(),
+            date_created: None,
+            date_modified: None,
+            date_added: None,
+            folder_id: Some(other.id),
+        };
+        let in_other = entry.clone().insert(conn).await.unwrap();
+        entry.path = "cat.png".to_string();
+        entry.folder_id = None;
+        let in_root = entry.insert(conn).await.unwrap();
+
+        // Each entry resolves inside its own folder
+        assert_eq!(
+            in_other.resolve_full_path(conn, &lib.path).await.unwrap(),
+            other_root.path().join("cats/cat.png")
+        );
+        assert_eq!(
+            in_root.resolve_full_path(conn, &lib.path).await.unwrap(),
+            lib.path.join("cat.png")
+        );
+        assert_eq!(
+            Entry::find_by_folder_full_path(conn, &other_root.path().join("cats/cat.png"))
+                .await
+                .unwrap(),
+            vec![in_other.clone()]
+        );
+        assert_eq!(
+            Entry::find_by_folder_full_path(conn, &lib.path.join("cats/cat.png"))
+                .await
+                .unwrap(),
+            vec![]
+        );
+
+        // Folders with entries can't be removed
+        assert!(matches!(
+            other.clone().delete(conn).await,
+            Err(FolderDeleteError::FolderNotEmpty { entry_count: 1, .. })
+        ));
+        in_other.delete(conn).await.unwrap();
+        other.delete(conn).await.unwrap();
+        assert_eq!(Folder::find_all(conn).await.unwrap().len(), 1);
+    }
+}
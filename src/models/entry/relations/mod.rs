@@ -1,2 +1,7 @@
+pub mod boolean_fields;
+pub mod datetime_fields;
+pub mod field_order;
+pub(crate) mod fields;
+pub mod folder;
 pub mod tags;
 pub mod text_fields;
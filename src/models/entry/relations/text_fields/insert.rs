@@ -0,0 +1,16 @@
+use crate::Entry;
+use crate::TextField;
+use crate::models::entry::relations::fields::insert_field;
+use crate::models::value_type::ValueTypeError;
+
+impl Entry {
+    /// Add a text field to the entry, after its other fields
+    pub async fn add_text_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        type_key: &str,
+        value: Option<&str>,
+    ) -> Result<TextField, ValueTypeError> {
+        insert_field(conn, "text_fields", self.id, type_key, value).await
+    }
+}
@@ -1,2 +1,4 @@
-mod insert;
+pub mod delete;
+pub mod insert;
 pub mod select;
+pub mod update;
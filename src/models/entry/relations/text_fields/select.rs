@@ -3,6 +3,7 @@ use crate::SqlxError;
 use crate::TextField;
 
 impl Entry {
+    /// Get the text fields of the entry, in display order
     pub async fn get_text_fields(
         &self,
         conn: &mut sqlx::SqliteConnection,
@@ -0,0 +1,16 @@
+use crate::Entry;
+use crate::SqlxError;
+use crate::TextField;
+use crate::models::entry::relations::fields::update_field;
+
+impl Entry {
+    /// Set the value of a text field of the entry. Returns `None` if the field doesn't belong to the entry
+    pub async fn update_text_field(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        field_id: i64,
+        value: Option<&str>,
+    ) -> Result<Option<TextField>, SqlxError> {
+        update_field(conn, "text_fields", self.id, field_id, value).await
+    }
+}
@@ -0,0 +1,52 @@
+use snafu::ResultExt as _;
+use sqlx::AssertSqlSafe;
+
+use crate::Entry;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
+
+impl Entry {
+    /// Save the entry in the database.
+    ///
+    /// This uses the schema capabilities cached on the connection. See [SchemaCapabilities::get_cached]
+    pub async fn save(&self, conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+        let schema = SchemaCapabilities::get_cached(conn).await?;
+        self.save_with_schema(conn, &schema).await
+    }
+
+    /// Save the entry in the database, only writing the columns the schema has
+    pub async fn save_with_schema(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        schema: &SchemaCapabilities,
+    ) -> Result<(), SqlxError> {
+        let columns = schema.entry_insert_columns();
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| format!("`{col}` = ${}", i + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "UPDATE `entries` SET {assignments} WHERE `id` = ${}",
+            columns.len() + 1
+        );
+
+        let mut query = sqlx::query(AssertSqlSafe(sql))
+            .bind(&self.path)
+            .bind(&self.filename)
+            .bind(&self.suffix)
+            .bind(self.date_created)
+            .bind(self.date_modified)
+            .bind(self.date_added);
+
+        if schema.entry_has_folder_id {
+            query = query.bind(self.folder_id);
+        }
+
+        query.bind(self.id).execute(conn).await.context(SqlxSnafu)?;
+
+        Ok(())
+    }
+}
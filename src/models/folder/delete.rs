@@ -0,0 +1,50 @@
+use snafu::ResultExt;
+use snafu::Snafu;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+
+impl Folder {
+    /// Remove the folder from the library.
+    ///
+    /// This refuses to delete a folder that still has entries, as it would leave them without a root
+    pub async fn delete(self, conn: &mut sqlx::SqliteConnection) -> Result<(), FolderDeleteError> {
+        let entry_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM `entries` WHERE `folder_id` = $1")
+                .bind(self.id)
+                .fetch_one(&mut *conn)
+                .await
+                .context(SqlxSnafu)
+                .context(SqlSnafu)?;
+
+        if entry_count != 0 {
+            return FolderNotEmptySnafu {
+                path: self.path,
+                entry_count,
+            }
+            .fail();
+        }
+
+        sqlx::query("DELETE FROM `folders` WHERE `id` = $1")
+            .bind(self.id)
+            .execute(conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        Ok(())
+    }
+}
+
+/// Error for [Folder::delete]
+#[derive(Debug, Snafu)]
+pub enum FolderDeleteError {
+    #[snafu(display("The folder `{path}` still has {entry_count} entries"))]
+    FolderNotEmpty { path: String, entry_count: i64 },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use snafu::ResultExt;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+
+impl Folder {
+    /// Register a new folder in the library, with a random uuid
+    pub async fn insert_new(
+        conn: &mut sqlx::SqliteConnection,
+        path: &Path,
+    ) -> Result<Self, SqlxError> {
+        sqlx::query_as("INSERT INTO `folders` (`path`, `uuid`) VALUES ($1, $2) RETURNING *;")
+            .bind(path.to_string_lossy().to_string())
+            .bind(uuid::Uuid::new_v4().to_string())
+            .fetch_one(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Get the folder with this path, or register it if it doesn't exist
+    pub async fn get_or_insert(
+        conn: &mut sqlx::SqliteConnection,
+        path: &Path,
+    ) -> Result<Self, SqlxError> {
+        match Self::find_by_path(conn, path).await? {
+            Some(folder) => Ok(folder),
+            None => Self::insert_new(conn, path).await,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use sqlx::FromRow;
+
+use crate::Entry;
+
+pub mod delete;
+pub mod insert;
+pub mod select;
+pub mod update;
+
+/// A root folder tracked by the library. Entry paths are relative to their folder
+#[derive(Debug, FromRow, Clone, PartialEq, Eq)]
+pub struct Folder {
+    pub id: i64,
+    pub path: String,
+    pub uuid: String,
+}
+
+impl Folder {
+    /// Get the path of the folder on the filesystem
+    pub fn get_path(&self) -> PathBuf {
+        PathBuf::from(&self.path)
+    }
+
+    /// Get the full path of an entry of this folder
+    pub fn get_entry_full_path(&self, entry: &Entry) -> PathBuf {
+        self.get_path().join(&entry.path)
+    }
+
+    /// Get the path of the file relative to this folder. Returns `None` if the file isn't inside the folder
+    pub fn get_relative_path(&self, full_path: &Path) -> Option<PathBuf> {
+        full_path
+            .strip_prefix(self.get_path())
+            .ok()
+            .map(Path::to_path_buf)
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use snafu::ResultExt;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::models::library::capabilities::SchemaCapabilities;
+
+impl Folder {
+    /// Get the folder by its id
+    pub async fn find_by_id(
+        conn: &mut sqlx::SqliteConnection,
+        id: i64,
+    ) -> Result<Option<Self>, SqlxError> {
+        sqlx::query_as("SELECT * FROM `folders` WHERE `id` = $1")
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Get the folder by its path
+    pub async fn find_by_path(
+        conn: &mut sqlx::SqliteConnection,
+        path: &Path,
+    ) -> Result<Option<Self>, SqlxError> {
+        sqlx::query_as("SELECT * FROM `folders` WHERE `path` = $1")
+            .bind(path.to_string_lossy().to_string())
+            .fetch_optional(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Get all the folders of the library
+    pub async fn find_all(conn: &mut sqlx::SqliteConnection) -> Result<Vec<Self>, SqlxError> {
+        sqlx::query_as("SELECT * FROM `folders` ORDER BY `id`")
+            .fetch_all(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Get the paths of all the folders of the library, by id. Empty on libraries without folders
+    pub async fn find_all_paths(
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<HashMap<i64, PathBuf>, SqlxError> {
+        if !SchemaCapabilities::get_cached(conn).await?.has_folders {
+            return Ok(HashMap::new());
+        }
+
+        Ok(Self::find_all(conn)
+            .await?
+            .into_iter()
+            .map(|folder| (folder.id, folder.get_path()))
+            .collect())
+    }
+
+    /// Get the folder that contains this full path.
+    ///
+    /// If folders are nested, the innermost one is returned
+    pub async fn find_containing(
+        conn: &mut sqlx::SqliteConnection,
+        full_path: &Path,
+    ) -> Result<Option<Self>, SqlxError> {
+        Ok(Self::find_all(conn)
+            .await?
+            .into_iter()
+            .filter(|folder| full_path.starts_with(folder.get_path()))
+            .max_by_key(|folder| folder.get_path().components().count()))
+    }
+}
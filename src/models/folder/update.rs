@@ -0,0 +1,20 @@
+use snafu::ResultExt;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+
+impl Folder {
+    /// Save the folder in the database
+    pub async fn update(&self, conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+        sqlx::query("UPDATE `folders` SET `path` = $1, `uuid` = $2 WHERE `id` = $3")
+            .bind(&self.path)
+            .bind(&self.uuid)
+            .bind(self.id)
+            .execute(conn)
+            .await
+            .context(SqlxSnafu)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+use core::sync::atomic::Ordering;
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use chrono::Timelike as _;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+use tracing::info;
+
+use crate::TSPoolError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+
+/// Path of the backup folder, relative to the library root. This is the same folder as the app
+pub const BACKUP_FOLDER: &str = ".TagStudio/backups";
+
+/// The number of backups kept by [Library::backup]
+pub const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+const BACKUP_PREFIX: &str = "ts_library_backup_";
+const BACKUP_DATE_FORMAT: &str = "%Y_%m_%d_%H%M%S";
+
+/// A copy of the library database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub date_created: NaiveDateTime,
+
+    /// The number of backups made before this one during the same second
+    pub counter: u32,
+}
+
+impl Backup {
+    /// Read a backup from its path. Returns `None` if the file name isn't one of a backup
+    pub fn from_path(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let date = name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(".sqlite")?;
+
+        // Backups made during the same second get a counter after the date
+        let (date, counter) = match date.get(17..) {
+            Some("") | None => (date, 0),
+            Some(counter) => (&date[..17], counter.strip_prefix('_')?.parse().ok()?),
+        };
+        let date_created = NaiveDateTime::parse_from_str(date, BACKUP_DATE_FORMAT).ok()?;
+
+        Some(Self {
+            path,
+            date_created,
+            counter,
+        })
+    }
+}
+
+impl Library {
+    pub fn get_backup_folder(&self) -> PathBuf {
+        self.path.join(BACKUP_FOLDER)
+    }
+
+    /// Save a timestamped copy of the database in the backup folder, and only keep the [DEFAULT_BACKUP_RETENTION] most recent ones
+    pub async fn backup(&self) -> Result<Backup, BackupError> {
+        self.backup_with_retention(Some(DEFAULT_BACKUP_RETENTION))
+            .await
+    }
+
+    /// Save a timestamped copy of the database in the backup folder.
+    ///
+    /// Once saved, only the `retention` most recent backups are kept. `None` keeps them all
+    pub async fn backup_with_retention(
+        &self,
+        retention: Option<usize>,
+    ) -> Result<Backup, BackupError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let backup = self.write_backup(conn).await?;
+
+        if let Some(retention) = retention {
+            self.prune_backups(retention)?;
+        }
+
+        Ok(backup)
+    }
+
+    /// Write a copy of the database in the backup folder using `VACUUM INTO`
+    pub(crate) async fn write_backup(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Backup, BackupError> {
+        if self.path.as_os_str().is_empty() {
+            return InMemorySnafu.fail();
+        }
+
+        let backup_folder = self.get_backup_folder();
+        std::fs::create_dir_all(&backup_folder).context(IoSnafu {
+            path: &backup_folder,
+        })?;
+
+        let date_created = chrono::Utc::now().naive_utc();
+        let date = date_created.format(BACKUP_DATE_FORMAT);
+        let mut path = backup_folder.join(format!("{BACKUP_PREFIX}{date}.sqlite"));
+        let mut counter = 0;
+        while path.try_exists().context(IoSnafu { path: &path })? {
+            counter += 1;
+            path = backup_folder.join(format!("{BACKUP_PREFIX}{date}_{counter}.sqlite"));
+        }
+
+        info!("Saving backup of the library to `{}`", path.display());
+        sqlx::query("VACUUM INTO $1")
+            .bind(path.to_string_lossy().to_string())
+            .execute(conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        Ok(Backup {
+            path,
+            date_created: date_created.with_nanosecond(0).unwrap_or(date_created),
+            counter,
+        })
+    }
+
+    /// Take a backup if [Library::set_auto_backup] is enabled. Called by the destructive bulk operations
+    pub(crate) async fn auto_backup(&self) -> Result<Option<Backup>, BackupError> {
+        if !self.auto_backup.load(Ordering::Relaxed) || self.path.as_os_str().is_empty() {
+            return Ok(None);
+        }
+
+        self.backup().await.map(Some)
+    }
+
+    /// Take a backup before running destructive bulk operations, like [Library::merge_duplicates]
+    pub fn set_auto_backup(&self, enabled: bool) {
+        self.auto_backup.store(enabled, Ordering::Relaxed);
+    }
+
+    /// List the backups of the library, from the most recent to the oldest
+    pub fn list_backups(&self) -> Result<Vec<Backup>, BackupError> {
+        let backup_folder = self.get_backup_folder();
+        if !backup_folder.try_exists().context(IoSnafu {
+            path: &backup_folder,
+        })? {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for file in std::fs::read_dir(&backup_folder).context(IoSnafu {
+            path: &backup_folder,
+        })? {
+            let file = file.context(IoSnafu {
+                path: &backup_folder,
+            })?;
+
+            if let Some(backup) = Backup::from_path(file.path()) {
+                backups.push(backup);
+            }
+        }
+
+        backups.sort_by(|a, b| {
+            b.date_created
+                .cmp(&a.date_created)
+                .then_with(|| b.counter.cmp(&a.counter))
+        });
+        Ok(backups)
+    }
+
+    /// Delete the oldest backups, keeping the `retention` most recent ones. Returns the deleted backups
+    pub fn prune_backups(&self, retention: usize) -> Result<Vec<Backup>, BackupError> {
+        let mut backups = self.list_backups()?;
+        let removed = backups.split_off(retention.min(backups.len()));
+
+        for backup in &removed {
+            info!("Removing old backup `{}`", backup.path.display());
+            std::fs::remove_file(&backup.path).context(IoSnafu { path: &backup.path })?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Replace the content of the library by the one of a backup.
+    ///
+    /// The schema is restored too, so a backup taken before a migration brings back the older version.
+    /// A backup of the current state is taken first, and returned
+    pub async fn restore(&self, backup: &Backup) -> Result<Backup, BackupError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let current = self.write_backup(conn).await?;
+
+        info!("Restoring the library from `{}`", backup.path.display());
+        sqlx::query("ATTACH DATABASE $1 AS ts_backup")
+            .bind(backup.path.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        // The foreign keys can only be toggled outside of a transaction
+        sqlx::raw_sql("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        let result = restore_from_attached(conn).await;
+
+        sqlx::raw_sql("PRAGMA foreign_keys = ON; DETACH DATABASE ts_backup;")
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        result.context(SqlSnafu)?;
+
+        Ok(current)
+    }
+}
+
+/// Copy the schema and data of the `ts_backup` database into `main`
+async fn restore_from_attached(conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+    let mut trans = conn.begin().await.context(SqlxSnafu)?;
+
+    let current_tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM main.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&mut *trans)
+    .await
+    .context(SqlxSnafu)?;
+
+    for table in current_tables {
+        sqlx::raw_sql(AssertSqlSafe(format!("DROP TABLE main.\"{table}\"")))
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)?;
+    }
+
+    // Tables first, then the indexes, triggers and views that depend on them
+    let objects: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT type, name, sql FROM ts_backup.sqlite_master
+        WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+        ORDER BY type != 'table', rowid",
+    )
+    .fetch_all(&mut *trans)
+    .await
+    .context(SqlxSnafu)?;
+
+    for (kind, name, sql) in objects {
+        sqlx::raw_sql(AssertSqlSafe(sql))
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)?;
+
+        if kind == "table" {
+            sqlx::raw_sql(AssertSqlSafe(format!(
+                "INSERT INTO main.\"{name}\" SELECT * FROM ts_backup.\"{name}\""
+            )))
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)?;
+        }
+    }
+
+    let has_sequence: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM ts_backup.sqlite_master WHERE name = 'sqlite_sequence'",
+    )
+    .fetch_one(&mut *trans)
+    .await
+    .context(SqlxSnafu)?;
+
+    if has_sequence {
+        sqlx::raw_sql(
+            "DELETE FROM main.sqlite_sequence;
+            INSERT INTO main.sqlite_sequence SELECT * FROM ts_backup.sqlite_sequence;",
+        )
+        .execute(&mut *trans)
+        .await
+        .context(SqlxSnafu)?;
+    }
+
+    trans.commit().await.context(SqlxSnafu)
+}
+
+/// Error for the backup operations
+#[derive(Debug, Snafu)]
+pub enum BackupError {
+    #[snafu(display("In memory libraries can't be backed up"))]
+    InMemory { backtrace: Backtrace },
+
+    #[snafu(display("Filesytem returned an error at `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Library;
+    use crate::Tag;
+
+    #[tokio::test]
+    pub async fn backup_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        let first = lib.backup_with_retention(None).await.unwrap();
+        assert!(first.path.exists());
+
+        let maxwell = {
+            let conn = &mut *lib.db.get().await.unwrap();
+            Tag::from("Maxwell").insert_tag(conn).await.unwrap()
+        };
+
+        let second = lib.backup_with_retention(None).await.unwrap();
+        assert_eq!(
+            lib.list_backups().unwrap(),
+            vec![second.clone(), first.clone()]
+        );
+
+        // Going back to the first backup removes the tag
+        let before_restore = lib.restore(&first).await.unwrap();
+        {
+            let conn = &mut *lib.db.get().await.unwrap();
+            assert!(Tag::find_by_id(conn, maxwell.id).await.unwrap().is_none());
+        }
+
+        // And the backup taken by the restore brings it back
+        lib.restore(&before_restore).await.unwrap();
+        {
+            let conn = &mut *lib.db.get().await.unwrap();
+            assert!(Tag::find_by_id(conn, maxwell.id).await.unwrap().is_some());
+        }
+
+        // Only the most recent ones are kept
+        let removed = lib.prune_backups(1).unwrap();
+        assert_eq!(removed.len(), 3);
+        assert!(!first.path.exists());
+        assert_eq!(lib.list_backups().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn same_second_backups_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        let backup_folder = lib.get_backup_folder();
+        fs::create_dir_all(&backup_folder).unwrap();
+        for name in ["", "_2", "_10"] {
+            fs::write(
+                backup_folder.join(format!("ts_library_backup_2025_01_02_030405{name}.sqlite")),
+                "",
+            )
+            .unwrap();
+        }
+
+        // The counters are compared as numbers, not as text
+        let counters = lib
+            .list_backups()
+            .unwrap()
+            .iter()
+            .map(|backup| backup.counter)
+            .collect::<Vec<_>>();
+        assert_eq!(counters, vec![10, 2, 0]);
+    }
+}
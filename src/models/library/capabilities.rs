@@ -0,0 +1,302 @@
+use futures::TryStreamExt as _;
+use snafu::ResultExt;
+use sqlx::FromRow;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+
+/// The optional parts of the database schema.
+///
+/// The schema isn't only tied to the version of the library, as libraries with the same version may have been created by different versions of the app.
+/// Queries that read, insert or update whole rows use this to know which columns they can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct SchemaCapabilities {
+    /// The version of the library, if it could be read
+    pub version: Option<i64>,
+
+    /// The library has a `folders` table
+    pub has_folders: bool,
+
+    /// The `entries` table has a `folder_id` column
+    pub entry_has_folder_id: bool,
+
+    /// The `tags` table has a `is_hidden` column
+    pub tag_has_is_hidden: bool,
+}
+
+impl SchemaCapabilities {
+    /// Read the capabilities from the database, without using the cache of the connection
+    pub async fn detect(conn: &mut sqlx::SqliteConnection) -> Result<Self, SqlxError> {
+        let version = Library::get_version(conn).await.ok();
+        let entry_columns = get_table_columns(conn, "entries").await?;
+        let tag_columns = get_table_columns(conn, "tags").await?;
+        let folder_columns = get_table_columns(conn, "folders").await?;
+
+        Ok(Self {
+            version,
+            has_folders: !folder_columns.is_empty(),
+            entry_has_folder_id: entry_columns.iter().any(|col| col == "folder_id"),
+            tag_has_is_hidden: tag_columns.iter().any(|col| col == "is_hidden"),
+        })
+    }
+
+    /// Get the capabilities of the connection's database.
+    ///
+    /// They are detected on the first call, then cached in a temporary table of the connection until the schema changes
+    pub async fn get_cached(conn: &mut sqlx::SqliteConnection) -> Result<Self, SqlxError> {
+        let cached = sqlx::raw_sql(
+            "CREATE TEMP TABLE IF NOT EXISTS `schema_capabilities_cache` (
+                `schema_version` INTEGER NOT NULL,
+                `version` INTEGER,
+                `has_folders` BOOLEAN NOT NULL,
+                `entry_has_folder_id` BOOLEAN NOT NULL,
+                `tag_has_is_hidden` BOOLEAN NOT NULL
+            );
+            SELECT `version`, `has_folders`, `entry_has_folder_id`, `tag_has_is_hidden`
+            FROM temp.`schema_capabilities_cache`
+            WHERE `schema_version` = (SELECT `schema_version` FROM pragma_schema_version);",
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+        if let Some(row) = cached {
+            return Self::from_row(&row).context(SqlxSnafu);
+        }
+
+        let schema = Self::detect(conn).await?;
+
+        sqlx::raw_sql("DELETE FROM temp.`schema_capabilities_cache`")
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)?;
+        sqlx::query(
+            "INSERT INTO temp.`schema_capabilities_cache`
+            VALUES ((SELECT `schema_version` FROM pragma_schema_version), $1, $2, $3, $4)",
+        )
+        .bind(schema.version)
+        .bind(schema.has_folders)
+        .bind(schema.entry_has_folder_id)
+        .bind(schema.tag_has_is_hidden)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+        Ok(schema)
+    }
+
+    /// The columns of `entries` that are written on insert or update, in bind order. `id` is excluded
+    pub fn entry_insert_columns(&self) -> Vec<&'static str> {
+        let mut columns = vec![
+            "path",
+            "filename",
+            "suffix",
+            "date_created",
+            "date_modified",
+            "date_added",
+        ];
+
+        if self.entry_has_folder_id {
+            columns.push("folder_id");
+        }
+
+        columns
+    }
+
+    /// The columns to select to read whole rows of `entries`. A missing `folder_id` is read as `NULL`
+    pub fn entry_select_columns(&self) -> String {
+        let folder_id = if self.entry_has_folder_id {
+            "`entries`.`folder_id`"
+        } else {
+            "NULL AS `folder_id`"
+        };
+
+        format!(
+            "`entries`.`id`, `entries`.`path`, `entries`.`filename`, `entries`.`suffix`, `entries`.`date_created`, `entries`.`date_modified`, `entries`.`date_added`, {folder_id}"
+        )
+    }
+
+    /// The columns of `tags` that are written on insert or update, in bind order. `id` is excluded
+    pub fn tag_write_columns(&self) -> Vec<&'static str> {
+        let mut columns = vec![
+            "name",
+            "shorthand",
+            "color_namespace",
+            "color_slug",
+            "is_category",
+            "icon",
+            "disambiguation_id",
+        ];
+
+        if self.tag_has_is_hidden {
+            columns.push("is_hidden");
+        }
+
+        columns
+    }
+
+    /// The columns to select to read whole rows of `tags`. A missing `is_hidden` is read as `false`
+    pub fn tag_select_columns(&self) -> String {
+        let is_hidden = if self.tag_has_is_hidden {
+            "`tags`.`is_hidden`"
+        } else {
+            "FALSE AS `is_hidden`"
+        };
+
+        format!(
+            "`tags`.`id`, `tags`.`name`, `tags`.`shorthand`, `tags`.`color_namespace`, `tags`.`color_slug`, `tags`.`is_category`, {is_hidden}, `tags`.`icon`, `tags`.`disambiguation_id`"
+        )
+    }
+}
+
+impl Library {
+    /// Get the capabilities of the library's schema. See [SchemaCapabilities::get_cached]
+    pub async fn schema(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<SchemaCapabilities, SqlxError> {
+        SchemaCapabilities::get_cached(conn).await
+    }
+}
+
+/// Get the name of the columns of a table. Returns an empty vec if the table doesn't exist
+async fn get_table_columns(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+) -> Result<Vec<String>, SqlxError> {
+    sqlx::query_scalar("SELECT `name` FROM pragma_table_info($1)")
+        .bind(table)
+        .fetch(conn)
+        .try_collect()
+        .await
+        .context(SqlxSnafu)
+}
+
+/// Create a list of `count` positional placeholders, starting at `$1`
+pub(crate) fn placeholders(count: usize) -> String {
+    (1..=count)
+        .map(|i| format!("${i}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::library::capabilities::SchemaCapabilities;
+    use crate::query::eq_entry_name::EqEntryName;
+    use crate::query::eq_tag_string::EqTagString;
+    use crate::query::trait_entry_filter::QueryEntryFilter as _;
+    use crate::query::trait_tag_filter::TagFilter as _;
+    use crate::tests::fixtures::raw_library::get_empty_library;
+
+    #[tokio::test]
+    pub async fn detect_without_folders_test() {
+        let lib = get_empty_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        let schema = lib.schema(conn).await.unwrap();
+        assert!(!schema.has_folders);
+        assert!(!schema.entry_has_folder_id);
+        assert!(schema.tag_has_is_hidden);
+
+        // Entries are written and read without `folder_id`
+        let entry = Entry::from_path(&lib, Path::new("cat.png"))
+            .unwrap()
+            .insert(conn)
+            .await
+            .unwrap();
+        let entries = EqEntryName("cat.png".to_string())
+            .fetch_all(conn)
+            .await
+            .unwrap();
+        assert_eq!(entries, vec![entry]);
+        assert_eq!(entries[0].folder_id, None);
+    }
+
+    #[tokio::test]
+    pub async fn write_with_folders_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        let schema = lib.schema(conn).await.unwrap();
+        assert_eq!(schema.version, Some(103));
+        assert!(schema.has_folders);
+        assert!(schema.entry_has_folder_id);
+
+        // The inserts must fill `folder_id`
+        let entry = Entry {
+            id: 0,
+            path: "cat.png".to_string(),
+            filename: "cat.png".to_string(),
+            suffix: "png".to_string(),
+            date_created: None,
+            date_modified: None,
+            date_added: None,
+            folder_id: None,
+        }
+        .insert_with_schema(conn, &schema)
+        .await
+        .unwrap();
+        assert_eq!(entry.path, "cat.png");
+        assert_eq!(entry.folder_id, Some(1));
+
+        let mut tag = Tag::from("Cat");
+        tag.is_category = true;
+        let mut tag = tag.insert_tag_with_schema(conn, &schema).await.unwrap();
+        assert!(tag.is_category);
+        assert!(!tag.is_hidden);
+
+        tag.is_hidden = true;
+        tag.update(conn).await.unwrap();
+        assert!(
+            Tag::find_by_id(conn, tag.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .is_hidden
+        );
+    }
+
+    #[tokio::test]
+    pub async fn write_without_is_hidden_test() {
+        let lib = get_empty_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        // Fill the cache of the connection
+        let schema = SchemaCapabilities::get_cached(conn).await.unwrap();
+        assert!(schema.tag_has_is_hidden);
+
+        sqlx::raw_sql("ALTER TABLE `tags` DROP COLUMN `is_hidden`")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let schema = SchemaCapabilities::detect(conn).await.unwrap();
+        assert!(!schema.tag_has_is_hidden);
+
+        // The cache is outdated by the schema change
+        assert_eq!(SchemaCapabilities::get_cached(conn).await.unwrap(), schema);
+
+        let mut tag = Tag::from("Cat").insert_tag(conn).await.unwrap();
+        tag.name = "Chat".to_string();
+        tag.update(conn).await.unwrap();
+
+        let tag = Tag::find_by_id(conn, tag.id).await.unwrap().unwrap();
+        assert_eq!(tag.name, "Chat");
+        assert!(!tag.is_hidden);
+
+        // The filters select the columns of the schema
+        let tags = EqTagString("chat".to_string())
+            .fetch_all(conn)
+            .await
+            .unwrap();
+        assert_eq!(tags, vec![tag]);
+    }
+}
@@ -0,0 +1,151 @@
+use core::time::Duration;
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::sqlite::SqliteConnectOptions;
+use tracing::debug;
+
+use crate::TSPoolError;
+use crate::client::conn_pool::PoolManager;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::models::library::Library;
+use crate::models::library::schema::LIBRARY_DEFAULT_DATA;
+use crate::models::library::schema::LIBRARY_SCHEMA;
+
+impl Library {
+    /// Create a new library in the given folder.
+    ///
+    /// This creates the `.TagStudio` folder, the database, and fills it with the same default data as the TagStudio app.
+    /// Fails if the folder already contains a library
+    pub async fn create(path: PathBuf) -> Result<Self, LibraryCreateError> {
+        if Self::is_folder_library_root(&path).context(IoSnafu)? {
+            return LibraryExistsSnafu {
+                path: path.display().to_string(),
+            }
+            .fail();
+        }
+
+        std::fs::create_dir_all(path.join(".TagStudio")).context(IoSnafu)?;
+        let root = path.canonicalize().context(IoSnafu)?;
+        let db_path = root.join(".TagStudio/ts_library.sqlite");
+
+        debug!("Creating DB `{}`", db_path.display());
+        let optconn = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .create_if_missing(true)
+            .busy_timeout(Duration::from_secs(600));
+        let pool = PoolManager::create_pool(optconn);
+
+        let lib = Self {
+            path: root,
+            db: pool,
+            auto_backup: Default::default(),
+        };
+
+        lib.init_database().await?;
+
+        Ok(lib)
+    }
+
+    /// Write the schema and the default data in an empty database
+    async fn init_database(&self) -> Result<(), LibraryCreateError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        sqlx::raw_sql(LIBRARY_SCHEMA)
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        sqlx::raw_sql(LIBRARY_DEFAULT_DATA)
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        // The app registers the library root as the first folder
+        Folder::insert_new(&mut trans, &self.path)
+            .await
+            .context(SqlSnafu)?;
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        Ok(())
+    }
+}
+
+/// Error for [Library::create]
+#[derive(Debug, Snafu)]
+pub enum LibraryCreateError {
+    #[snafu(display("There is already a library in: {path}"))]
+    LibraryExists { path: String, backtrace: Backtrace },
+
+    #[snafu(display("Filesytem returned an error"))]
+    IoError {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt as _;
+
+    use crate::Library;
+    use crate::Tag;
+
+    #[tokio::test]
+    pub async fn create_library_test() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        assert!(Library::is_folder_library_root(dir.path()).unwrap());
+        drop(lib);
+
+        // The crate must be able to open it back
+        let lib = Library::try_open_library(dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        let version: i64 =
+            sqlx::query_scalar("SELECT `value` FROM `versions` WHERE `key` = 'CURRENT'")
+                .fetch_one(&mut *conn)
+                .await
+                .unwrap();
+        assert_eq!(version, 103);
+
+        let meta = Tag::find_by_id(conn, 2).await.unwrap().unwrap();
+        assert!(meta.is_category);
+        assert!(
+            meta.get_children(conn)
+                .try_any(async |tag| tag.name == "Favorite")
+                .await
+                .unwrap()
+        );
+
+        // New tags are created after the reserved range
+        let cat = Tag::from("Cat").insert_tag(conn).await.unwrap();
+        assert_eq!(cat.id, 1000);
+
+        // Can't create a library twice
+        assert!(Library::create(dir.path().to_path_buf()).await.is_err());
+    }
+}
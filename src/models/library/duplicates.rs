@@ -0,0 +1,261 @@
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+use tracing::debug;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::entry::delete::FileDeletion;
+use crate::models::entry::fs::merge_entry::MergeEntryError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+use crate::models::library::backup::BackupError;
+
+impl Library {
+    /// Find the entries that are byte identical copies of each other.
+    ///
+    /// Only files sharing the same size get hashed, and the hashes are kept in the library's [HashCache](crate::models::library::hash_cache::HashCache) so reruns are cheap.
+    /// Unlinked entries are skipped
+    pub async fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>, DuplicatesError> {
+        // The connection is released before the slow hashing
+        let entries = {
+            let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+            let folders = self.get_folder_paths(conn).await.context(SqlSnafu)?;
+            let entries: Vec<Entry> = sqlx::query_as("SELECT * FROM `entries` ORDER BY `id`")
+                .fetch_all(&mut *conn)
+                .await
+                .context(SqlxSnafu)
+                .context(SqlSnafu)?;
+
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let full_path = self.resolve_entry_path(&folders, &entry);
+                    (entry, full_path)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut cache = self.load_hash_cache();
+        let (mut cache, by_hash) = tokio::task::spawn_blocking(move || {
+            // Group by size first, as files of different sizes can't be identical
+            let mut by_size: HashMap<u64, Vec<(Entry, PathBuf)>> = HashMap::new();
+            for (entry, full_path) in entries {
+                let Ok(metadata) = std::fs::metadata(&full_path) else {
+                    continue;
+                };
+
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push((entry, full_path));
+            }
+
+            let mut by_hash: HashMap<String, Vec<Entry>> = HashMap::new();
+            for (entry, full_path) in by_size
+                .into_values()
+                .filter(|group| group.len() > 1)
+                .flatten()
+            {
+                debug!("Hashing `{}`", full_path.display());
+                let hash = match cache.get_hash(&full_path) {
+                    Ok(hash) => hash,
+                    // The file got removed during the scan
+                    Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                    Err(err) => return Err(err).context(IoSnafu { path: full_path }),
+                };
+                by_hash.entry(hash).or_default().push(entry);
+            }
+
+            Ok::<_, DuplicatesError>((cache, by_hash))
+        })
+        .await
+        .context(HashingSnafu)??;
+
+        let cache_path = self.get_hash_cache_path();
+        cache
+            .save(&cache_path)
+            .context(HashCacheSnafu { path: cache_path })?;
+
+        let mut groups = by_hash
+            .into_iter()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(hash, mut entries)| {
+                entries.sort_by_key(|entry| entry.id);
+                DuplicateGroup { hash, entries }
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|group| group.entries[0].id);
+
+        Ok(groups)
+    }
+
+    /// Merge each group of duplicates into its oldest entry, using [Entry::merge_entry].
+    ///
+    /// The tags and fields of the copies are moved to the kept entry, and the files of the copies are removed as told by `file`.
+    /// Returns the kept entries. A backup is taken first if [Library::set_auto_backup] is enabled
+    pub async fn merge_duplicates(
+        &self,
+        groups: Vec<DuplicateGroup>,
+        file: FileDeletion,
+    ) -> Result<Vec<Entry>, DuplicatesError> {
+        self.auto_backup().await.context(BackupSnafu)?;
+
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let mut kept = Vec::with_capacity(groups.len());
+
+        for group in groups {
+            let mut entries = group.entries.into_iter();
+            let Some(main) = entries.next() else {
+                continue;
+            };
+
+            for other in entries {
+                debug!("Merging `{}` into `{}`", other.path, main.path);
+                main.merge_entry_with_deletion(conn, other, &self.path, file)
+                    .await
+                    .context(MergeSnafu)?;
+            }
+
+            kept.push(main);
+        }
+
+        Ok(kept)
+    }
+}
+
+/// Entries with the same file content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// The blake3 hash of the content
+    pub hash: String,
+
+    /// The entries, oldest first
+    pub entries: Vec<Entry>,
+}
+
+/// Error for [Library::find_duplicates] and [Library::merge_duplicates]
+#[derive(Debug, Snafu)]
+pub enum DuplicatesError {
+    #[snafu(display("Couldn't read `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't write the hash cache at `{}`", path.display()))]
+    HashCacheError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The hashing of the files panicked"))]
+    HashingError {
+        source: tokio::task::JoinError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't backup the library"))]
+    BackupError {
+        #[snafu(backtrace)]
+        source: BackupError,
+    },
+
+    #[snafu(display("Couldn't merge the duplicates"))]
+    MergeError {
+        #[snafu(backtrace)]
+        source: MergeEntryError,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::entry::Entry;
+    use crate::models::entry::delete::FileDeletion;
+
+    #[tokio::test]
+    pub async fn merge_duplicates_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let lib = Library::create(root.to_path_buf()).await.unwrap();
+
+        fs::create_dir_all(root.join("copy")).unwrap();
+        fs::write(root.join("maxwell.png"), "maxwell").unwrap();
+        fs::write(root.join("copy/maxwell.png"), "maxwell").unwrap();
+        fs::write(root.join("copy/maxwell (1).png"), "maxwell").unwrap();
+        fs::write(root.join("doge.png"), "doge").unwrap();
+        fs::write(root.join("oiia.png"), "oiia").unwrap();
+        lib.refresh().await.unwrap();
+
+        let groups = lib.find_duplicates().await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].entries.len(), 3);
+        assert!(lib.get_hash_cache_path().exists());
+
+        // The cache is reused: a tampered hash is returned as is, as the files didn't change
+        let cache_path = lib.get_hash_cache_path();
+        let mut cache: Value =
+            serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+        for cached in cache.as_object_mut().unwrap().values_mut() {
+            if cached["hash"] == groups[0].hash.as_str() {
+                cached["hash"] = Value::from("cached");
+            }
+        }
+        fs::write(&cache_path, cache.to_string()).unwrap();
+
+        let groups = lib.find_duplicates().await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].hash, "cached");
+        assert_eq!(groups[0].entries.len(), 3);
+
+        // Tag a copy, and check that the tag is kept after the merge
+        let copy = groups[0].entries[2].clone();
+        {
+            let conn = &mut *lib.db.get().await.unwrap();
+            let cat = Tag::from("Cat").insert_tag(conn).await.unwrap();
+            copy.add_tag(conn, &cat).await.unwrap();
+        }
+
+        lib.set_auto_backup(true);
+        let kept = lib
+            .merge_duplicates(groups, FileDeletion::Keep)
+            .await
+            .unwrap();
+        assert_eq!(lib.list_backups().unwrap().len(), 1);
+        assert_eq!(kept.len(), 1);
+
+        // The files of the copies are left alone
+        assert!(root.join("copy/maxwell.png").exists());
+        assert!(root.join("copy/maxwell (1).png").exists());
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        let tags = kept[0].get_tags(conn).await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert!(Entry::find_by_id(conn, copy.id).await.unwrap().is_none());
+        assert!(lib.find_duplicates().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::models::library::Library;
+
+/// The path of the hash cache, relative to the library root
+pub const HASH_CACHE_PATH: &str = ".TagStudio/hash_cache.json";
+
+/// A cache of the size and content hash of the library files.
+///
+/// Files are keyed on their full path. The sizes are recorded by [Library::refresh], so the size of a file is still known once it went missing.
+/// The hashes are computed on demand, and only reused if the size and modification date didn't change
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HashCache {
+    files: HashMap<String, CachedFile>,
+    changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedFile {
+    size: u64,
+    modified: u64,
+
+    /// `None` if the file wasn't hashed since it last changed
+    hash: Option<String>,
+}
+
+impl HashCache {
+    /// Read a cache file. A missing or unreadable cache is treated as empty, as it can always be rebuilt
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        let Ok(Value::Object(files)) = serde_json::from_str(&content) else {
+            return Self::default();
+        };
+
+        let files = files
+            .into_iter()
+            .filter_map(|(path, value)| {
+                Some((
+                    path,
+                    CachedFile {
+                        size: value.get("size")?.as_u64()?,
+                        modified: value.get("modified")?.as_u64()?,
+                        hash: value
+                            .get("hash")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    },
+                ))
+            })
+            .collect();
+
+        Self {
+            files,
+            changed: false,
+        }
+    }
+
+    /// Write the cache, if it has changed since it got loaded
+    pub fn save(&mut self, path: &Path) -> Result<(), io::Error> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let files = self
+            .files
+            .iter()
+            .map(|(path, cached)| {
+                (
+                    path.clone(),
+                    json!({"size": cached.size, "modified": cached.modified, "hash": cached.hash}),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        std::fs::write(path, Value::Object(files).to_string())?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Record the size and modification date of a file. Its cached hash is dropped if the file changed
+    pub fn record(&mut self, full_path: &Path, metadata: &Metadata) {
+        let size = metadata.len();
+        let modified = get_modified(metadata);
+
+        let key = full_path.to_string_lossy().to_string();
+        if let Some(cached) = self.files.get(&key)
+            && cached.size == size
+            && cached.modified == modified
+        {
+            return;
+        }
+
+        self.files.insert(
+            key,
+            CachedFile {
+                size,
+                modified,
+                hash: None,
+            },
+        );
+        self.changed = true;
+    }
+
+    /// Get the hash of a file, computing it if the cached one is outdated.
+    ///
+    /// This reads the whole file, so it should be called from a blocking task
+    pub fn get_hash(&mut self, full_path: &Path) -> Result<String, io::Error> {
+        let metadata = std::fs::metadata(full_path)?;
+        self.record(full_path, &metadata);
+
+        let key = full_path.to_string_lossy().to_string();
+        if let Some(hash) = self.files.get(&key).and_then(|cached| cached.hash.clone()) {
+            return Ok(hash);
+        }
+
+        let mut hasher = blake3::Hasher::new();
+        io::copy(&mut File::open(full_path)?, &mut hasher)?;
+        let hash = hasher.finalize().to_hex().to_string();
+
+        if let Some(cached) = self.files.get_mut(&key) {
+            cached.hash = Some(hash.clone());
+        }
+        self.changed = true;
+
+        Ok(hash)
+    }
+
+    /// Forget the files for which `keep` returns false
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        let len = self.files.len();
+        self.files.retain(|path, _| keep(Path::new(path)));
+
+        if self.files.len() != len {
+            self.changed = true;
+        }
+    }
+
+    /// Get the last known size of a file, even if it doesn't exist anymore
+    pub fn get_size(&self, full_path: &Path) -> Option<u64> {
+        self.files
+            .get(full_path.to_string_lossy().as_ref())
+            .map(|cached| cached.size)
+    }
+
+    /// Get the last known hash of a file, even if it doesn't exist anymore
+    pub fn get_cached_hash(&self, full_path: &Path) -> Option<&str> {
+        self.files
+            .get(full_path.to_string_lossy().as_ref())
+            .and_then(|cached| cached.hash.as_deref())
+    }
+}
+
+/// The modification date of a file, in nanoseconds since the epoch
+fn get_modified(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|dur| dur.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+impl Library {
+    pub fn get_hash_cache_path(&self) -> PathBuf {
+        self.path.join(HASH_CACHE_PATH)
+    }
+
+    /// Load the hash cache of the library
+    pub fn load_hash_cache(&self) -> HashCache {
+        HashCache::load(&self.get_hash_cache_path())
+    }
+}
@@ -0,0 +1,300 @@
+use std::path::Path;
+
+use crate::Entry;
+use crate::models::library::Library;
+
+/// The path of the ignore file, relative to the library root
+pub const IGNORE_FILE_PATH: &str = ".TagStudio/.ts_ignore";
+
+/// The rules of a `.ts_ignore` file.
+///
+/// The format follows `.gitignore`:
+/// - Empty lines and lines starting with `#` are skipped. Surrounding whitespace is trimmed
+/// - `*` matches anything but `/`, `?` a single character, and `[a-z]` a set of characters
+/// - `**` matches across folders
+/// - A leading `!` re-includes paths excluded by a previous rule
+/// - A trailing `/` only matches folders
+/// - Patterns with a `/` are relative to the library root. Others match at any depth
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Parse the content of an ignore file
+    pub fn parse(content: &str) -> Self {
+        Self {
+            rules: content.lines().filter_map(IgnoreRule::parse).collect(),
+        }
+    }
+
+    /// Read an ignore file. A missing file has no rules
+    pub fn from_file(path: &Path) -> Result<Self, std::io::Error> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Self::parse(&content)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Return true if the path is ignored. The path is relative to the library root, with `/` separators.
+    ///
+    /// Like git, a path inside an ignored folder is always ignored
+    pub fn is_ignored_path(&self, path: &str, is_dir: bool) -> bool {
+        let components = path
+            .split('/')
+            .filter(|comp| !comp.is_empty())
+            .collect::<Vec<_>>();
+
+        (1..=components.len()).any(|i| {
+            let sub_path = components[..i].join("/");
+            let sub_is_dir = i < components.len() || is_dir;
+
+            self.matches(&sub_path, sub_is_dir)
+        })
+    }
+
+    /// Return true if the file of the entry is ignored
+    pub fn is_ignored(&self, entry: &Entry) -> bool {
+        self.is_ignored_path(&entry.path, false)
+    }
+
+    /// Return whether the last rule that matches the path excludes it
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.chars().collect::<Vec<_>>();
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+impl Library {
+    /// Load the `.ts_ignore` rules of the library
+    pub fn get_ignore_rules(&self) -> Result<IgnoreRules, std::io::Error> {
+        IgnoreRules::from_file(&self.path.join(IGNORE_FILE_PATH))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnoreRule {
+    tokens: Vec<GlobToken>,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<Self> {
+        let mut line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let negated = line.starts_with('!');
+        if negated {
+            line = &line[1..];
+        } else if line.starts_with("\\#") || line.starts_with("\\!") {
+            line = &line[1..];
+        }
+
+        let dir_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        if line.is_empty() {
+            return None;
+        }
+
+        let anchored = line.contains('/');
+        let mut tokens = Vec::new();
+        if !anchored {
+            tokens.push(GlobToken::AnyFolders);
+        }
+        tokens.extend(GlobToken::parse(line.trim_start_matches('/')));
+
+        Some(Self {
+            tokens,
+            negated,
+            dir_only,
+        })
+    }
+
+    fn matches(&self, path: &[char], is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && GlobToken::matches(&self.tokens, path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobToken {
+    Literal(char),
+
+    /// `?`
+    AnyChar,
+
+    /// `*`
+    AnyChars,
+
+    /// `**`
+    AnyPath,
+
+    /// `**/`, which also matches no folder at all
+    AnyFolders,
+
+    /// `[...]`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl GlobToken {
+    fn parse(pattern: &str) -> Vec<Self> {
+        let chars = pattern.chars().collect::<Vec<_>>();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if chars.get(i + 2) == Some(&'/') {
+                        tokens.push(Self::AnyFolders);
+                        i += 3;
+                    } else {
+                        tokens.push(Self::AnyPath);
+                        i += 2;
+                    }
+                }
+                '*' => {
+                    tokens.push(Self::AnyChars);
+                    i += 1;
+                }
+                '?' => {
+                    tokens.push(Self::AnyChar);
+                    i += 1;
+                }
+                '[' => match Self::parse_class(&chars[i + 1..]) {
+                    Some((class, len)) => {
+                        tokens.push(class);
+                        i += len + 1;
+                    }
+                    None => {
+                        tokens.push(Self::Literal('['));
+                        i += 1;
+                    }
+                },
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(Self::Literal(chars[i + 1]));
+                    i += 2;
+                }
+                c => {
+                    tokens.push(Self::Literal(c));
+                    i += 1;
+                }
+            }
+        }
+
+        tokens
+    }
+
+    /// Parse the inside of a `[...]` class. Returns the token and the number of chars read, including the closing `]`
+    fn parse_class(chars: &[char]) -> Option<(Self, usize)> {
+        let mut i = 0;
+        let negated = matches!(chars.first(), Some('!' | '^'));
+        if negated {
+            i += 1;
+        }
+
+        let mut ranges = Vec::new();
+        let start = i;
+        while i < chars.len() {
+            // A `]` right after the opening is a literal
+            if chars[i] == ']' && i != start {
+                return Some((Self::Class { negated, ranges }, i + 1));
+            }
+
+            if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+
+        None
+    }
+
+    fn matches(tokens: &[Self], path: &[char]) -> bool {
+        let Some((token, rest)) = tokens.split_first() else {
+            return path.is_empty();
+        };
+
+        match token {
+            Self::Literal(c) => path.first() == Some(c) && Self::matches(rest, &path[1..]),
+            Self::AnyChar => {
+                path.first().is_some_and(|c| *c != '/') && Self::matches(rest, &path[1..])
+            }
+            Self::Class { negated, ranges } => {
+                path.first().is_some_and(|c| {
+                    *c != '/' && ranges.iter().any(|(low, high)| low <= c && c <= high) != *negated
+                }) && Self::matches(rest, &path[1..])
+            }
+            Self::AnyChars => {
+                let max = path.iter().position(|c| *c == '/').unwrap_or(path.len());
+                (0..=max).any(|i| Self::matches(rest, &path[i..]))
+            }
+            Self::AnyPath => (0..=path.len()).any(|i| Self::matches(rest, &path[i..])),
+            Self::AnyFolders => {
+                Self::matches(rest, path)
+                    || path
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, c)| **c == '/')
+                        .any(|(i, _)| Self::matches(rest, &path[i + 1..]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::library::ignore_rules::IgnoreRules;
+
+    #[test]
+    pub fn ignore_rules_test() {
+        let rules = IgnoreRules::parse(
+            "# Comment
+
+            *.txt
+            !keep.txt
+            /build/
+            cache/
+            docs/**/*.md
+            [Tt]humbs.db
+            ",
+        );
+
+        assert!(rules.is_ignored_path("notes.txt", false));
+        assert!(rules.is_ignored_path("a/b/notes.txt", false));
+        assert!(!rules.is_ignored_path("a/keep.txt", false));
+        assert!(!rules.is_ignored_path("notes.txt.png", false));
+
+        // Anchored and folder only rules
+        assert!(rules.is_ignored_path("build/cat.png", false));
+        assert!(!rules.is_ignored_path("src/build/cat.png", false));
+        assert!(!rules.is_ignored_path("build", false));
+        assert!(rules.is_ignored_path("a/cache/cat.png", false));
+
+        assert!(rules.is_ignored_path("docs/readme.md", false));
+        assert!(rules.is_ignored_path("docs/a/b/readme.md", false));
+        assert!(!rules.is_ignored_path("readme.md", false));
+
+        assert!(rules.is_ignored_path("a/thumbs.db", false));
+        assert!(rules.is_ignored_path("Thumbs.db", false));
+        assert!(!rules.is_ignored_path("humbs.db", false));
+    }
+}
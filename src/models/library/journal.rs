@@ -0,0 +1,392 @@
+use core::ops::Deref;
+use core::ops::DerefMut;
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use deadpool::managed::Object;
+use itertools::Itertools as _;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+use sqlx::FromRow;
+use tracing::debug;
+
+use crate::TSPoolError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+
+/// Path of the journal database, relative to the library root.
+///
+/// It's kept out of `ts_library.sqlite` so the app's schema stays untouched
+pub const JOURNAL_PATH: &str = ".TagStudio/ts_journal.sqlite";
+
+/// The tables whose changes are recorded in the journal
+const JOURNALED_TABLES: [&str; 9] = [
+    "tags",
+    "tag_aliases",
+    "tag_parents",
+    "tag_entries",
+    "entries",
+    "folders",
+    "text_fields",
+    "datetime_fields",
+    "boolean_fields",
+];
+
+const JOURNAL_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS ts_journal.journal_operations (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    label VARCHAR NOT NULL,
+    date_created DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+CREATE TABLE IF NOT EXISTS ts_journal.journal_changes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    operation_id INTEGER NOT NULL,
+    statement VARCHAR NOT NULL,
+    FOREIGN KEY(operation_id) REFERENCES journal_operations (id)
+);";
+
+/// An operation recorded in the journal
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct JournalOperation {
+    pub id: i64,
+    pub label: String,
+    pub date_created: chrono::NaiveDateTime,
+
+    /// The number of row changes made by the operation
+    pub change_count: i64,
+}
+
+/// A connection that records the inverse of every change it makes in the journal, so they can be reverted with [Library::undo].
+///
+/// Only the database is journaled. Files moved or deleted on disk aren't restored.
+///
+/// The connection is removed from the pool, and closed once dropped
+pub struct JournalConnection {
+    conn: sqlx::SqliteConnection,
+}
+
+impl JournalConnection {
+    /// Start a new operation. The following changes will be undone together
+    pub async fn start_operation(&mut self, label: &str) -> Result<i64, SqlxError> {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO ts_journal.journal_operations (label) VALUES ($1) RETURNING id",
+        )
+        .bind(label)
+        .fetch_one(&mut self.conn)
+        .await
+        .context(SqlxSnafu)?;
+
+        // The triggers read the operation from the connection, so concurrent journals don't mix their changes
+        sqlx::query(
+            "INSERT OR REPLACE INTO temp.journal_current_operation (rowid, id) VALUES (1, $1)",
+        )
+        .bind(id)
+        .execute(&mut self.conn)
+        .await
+        .context(SqlxSnafu)?;
+
+        Ok(id)
+    }
+}
+
+impl Deref for JournalConnection {
+    type Target = sqlx::SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl DerefMut for JournalConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+impl Library {
+    pub fn get_journal_path(&self) -> PathBuf {
+        self.path.join(JOURNAL_PATH)
+    }
+
+    /// Get a connection that records its changes in the journal, under a new operation named `label`
+    pub async fn journal(&self, label: &str) -> Result<JournalConnection, JournalError> {
+        let mut conn = JournalConnection {
+            conn: self.take_journal_connection().await?,
+        };
+
+        install_triggers(&mut conn).await.context(SqlSnafu)?;
+        conn.start_operation(label).await.context(SqlSnafu)?;
+
+        Ok(conn)
+    }
+
+    /// Get the operations recorded in the journal, from the most recent to the oldest
+    pub async fn journal_history(&self) -> Result<Vec<JournalOperation>, JournalError> {
+        let conn = &mut self.take_journal_connection().await?;
+
+        get_last_operations(conn, -1).await.context(SqlSnafu)
+    }
+
+    /// Revert the `n` last operations of the journal. Returns the reverted operations
+    pub async fn undo(&self, n: usize) -> Result<Vec<JournalOperation>, JournalError> {
+        let conn = &mut self.take_journal_connection().await?;
+        let operations = get_last_operations(conn, n as i64)
+            .await
+            .context(SqlSnafu)?;
+
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        for operation in &operations {
+            debug!("Undoing operation `{}`", operation.label);
+
+            let statements: Vec<String> = sqlx::query_scalar(
+                "SELECT statement FROM ts_journal.journal_changes WHERE operation_id = $1 ORDER BY id DESC",
+            )
+            .bind(operation.id)
+            .fetch_all(&mut *trans)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+            for statement in statements {
+                sqlx::raw_sql(AssertSqlSafe(statement))
+                    .execute(&mut *trans)
+                    .await
+                    .context(SqlxSnafu)
+                    .context(SqlSnafu)?;
+            }
+
+            sqlx::raw_sql(AssertSqlSafe(format!(
+                "DELETE FROM ts_journal.journal_changes WHERE operation_id = {0};
+                DELETE FROM ts_journal.journal_operations WHERE id = {0};",
+                operation.id
+            )))
+            .execute(&mut *trans)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+        }
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        Ok(operations)
+    }
+
+    /// Remove all the operations from the journal
+    pub async fn clear_journal(&self) -> Result<(), JournalError> {
+        let conn = &mut self.take_journal_connection().await?;
+
+        sqlx::raw_sql(
+            "DELETE FROM ts_journal.journal_changes;
+            DELETE FROM ts_journal.journal_operations;",
+        )
+        .execute(conn)
+        .await
+        .context(SqlxSnafu)
+        .context(SqlSnafu)?;
+
+        Ok(())
+    }
+
+    /// Take a connection out of the pool, and attach the journal database to it.
+    ///
+    /// The connection isn't given back to the pool, so the attached database and the triggers don't leak to other users
+    async fn take_journal_connection(&self) -> Result<sqlx::SqliteConnection, JournalError> {
+        let mut conn = Object::take(self.db.get().await.context(SqlPoolSnafu)?);
+
+        sqlx::query("ATTACH DATABASE $1 AS ts_journal")
+            .bind(self.get_journal_path().to_string_lossy().to_string())
+            .execute(&mut conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        sqlx::raw_sql(JOURNAL_SCHEMA)
+            .execute(&mut conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        Ok(conn)
+    }
+}
+
+async fn get_last_operations(
+    conn: &mut sqlx::SqliteConnection,
+    limit: i64,
+) -> Result<Vec<JournalOperation>, SqlxError> {
+    sqlx::query_as(
+        "SELECT
+            journal_operations.*,
+            (SELECT COUNT(*) FROM ts_journal.journal_changes WHERE operation_id = journal_operations.id) AS change_count
+        FROM ts_journal.journal_operations
+        ORDER BY id DESC
+        LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+    .context(SqlxSnafu)
+}
+
+/// Create the temporary triggers that record the inverse of each change.
+///
+/// Temporary triggers live with the connection, and are the only ones allowed to write in another database
+async fn install_triggers(conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+    sqlx::raw_sql(
+        "CREATE TEMP TABLE IF NOT EXISTS journal_current_operation (id INTEGER NOT NULL)",
+    )
+    .execute(&mut *conn)
+    .await
+    .context(SqlxSnafu)?;
+
+    for table in JOURNALED_TABLES {
+        // (name, pk)
+        let columns: Vec<(String, i64)> =
+            sqlx::query_as("SELECT name, pk FROM pragma_table_info($1) ORDER BY cid")
+                .bind(table)
+                .fetch_all(&mut *conn)
+                .await
+                .context(SqlxSnafu)?;
+
+        if columns.is_empty() {
+            continue;
+        }
+
+        let mut keys = columns
+            .iter()
+            .filter(|(_, pk)| *pk != 0)
+            .map(|(name, _)| name.as_str())
+            .collect_vec();
+        if keys.is_empty() {
+            keys = columns.iter().map(|(name, _)| name.as_str()).collect_vec();
+        }
+
+        let names = columns
+            .iter()
+            .map(|(name, _)| format!("\"{name}\""))
+            .join(", ");
+        let old_values = columns
+            .iter()
+            .map(|(name, _)| format!("quote(OLD.\"{name}\")"))
+            .join(" || ', ' || ");
+        let old_assignments = columns
+            .iter()
+            .map(|(name, _)| format!("'\"{name}\" = ' || quote(OLD.\"{name}\")"))
+            .join(" || ', ' || ");
+        let new_keys = keys
+            .iter()
+            .map(|name| format!("'\"{name}\" IS ' || quote(NEW.\"{name}\")"))
+            .join(" || ' AND ' || ");
+
+        let inverse_insert = format!("'DELETE FROM \"{table}\" WHERE ' || {new_keys}");
+        let inverse_delete =
+            format!("'INSERT INTO \"{table}\" ({names}) VALUES (' || {old_values} || ')'");
+        let inverse_update =
+            format!("'UPDATE \"{table}\" SET ' || {old_assignments} || ' WHERE ' || {new_keys}");
+
+        for (event, inverse) in [
+            ("INSERT", inverse_insert),
+            ("DELETE", inverse_delete),
+            ("UPDATE", inverse_update),
+        ] {
+            sqlx::raw_sql(AssertSqlSafe(format!(
+                "CREATE TEMP TRIGGER IF NOT EXISTS \"ts_journal_{table}_{event}\" AFTER {event} ON \"{table}\"
+                BEGIN
+                    INSERT INTO journal_changes (operation_id, statement)
+                    VALUES ((SELECT id FROM temp.journal_current_operation), {inverse});
+                END;"
+            )))
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Error for the journal operations
+#[derive(Debug, Snafu)]
+pub enum JournalError {
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Library;
+    use crate::Tag;
+
+    #[tokio::test]
+    pub async fn undo_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        let mut conn = lib.journal("Add tag").await.unwrap();
+        let mut tag = Tag::from("Maxwell").insert_tag(&mut conn).await.unwrap();
+
+        conn.start_operation("Rename tag").await.unwrap();
+        tag.name = "Doge".to_string();
+        tag.update(&mut conn).await.unwrap();
+
+        conn.start_operation("Delete tag").await.unwrap();
+        tag.clone().delete(&mut conn).await.unwrap();
+        drop(conn);
+
+        let history = lib.journal_history().await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|op| op.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Delete tag", "Rename tag", "Add tag"]
+        );
+
+        // Bring back the tag
+        lib.undo(1).await.unwrap();
+        let conn = &mut *lib.db.get().await.unwrap();
+        let restored = Tag::find_by_id(conn, tag.id).await.unwrap().unwrap();
+        assert_eq!(restored.name, "Doge");
+
+        // Revert the rename and the creation
+        let undone = lib.undo(2).await.unwrap();
+        assert_eq!(undone.len(), 2);
+        assert!(Tag::find_by_id(conn, tag.id).await.unwrap().is_none());
+        assert!(lib.journal_history().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    pub async fn concurrent_journals_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        let mut first = lib.journal("Add Maxwell").await.unwrap();
+        let mut second = lib.journal("Add Doge").await.unwrap();
+
+        // The first journal writes after the second one started its operation
+        Tag::from("Maxwell").insert_tag(&mut first).await.unwrap();
+        Tag::from("Doge").insert_tag(&mut second).await.unwrap();
+        drop((first, second));
+
+        let history = lib.journal_history().await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|op| (op.label.as_str(), op.change_count))
+                .collect::<Vec<_>>(),
+            vec![("Add Doge", 1), ("Add Maxwell", 1)]
+        );
+    }
+}
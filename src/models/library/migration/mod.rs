@@ -0,0 +1,267 @@
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use tracing::info;
+
+use crate::TSPoolError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+use crate::models::library::LibraryOpenError;
+use crate::models::library::backup::BackupError;
+use crate::models::library::migration::steps::MigrationStep;
+
+pub mod steps;
+
+/// The database version that the crate is made for
+pub const LIBRARY_VERSION: i64 = 103;
+
+/// The oldest database version that can be migrated. Older libraries are JSON based, and need to be opened by the app first
+pub const MIN_MIGRATABLE_VERSION: i64 = 6;
+
+impl Library {
+    /// Get the database version of the library.
+    ///
+    /// This reads the `versions` table, and fallback on the legacy `DB_VERSION` preference for older libraries
+    pub async fn get_version(conn: &mut sqlx::SqliteConnection) -> Result<i64, SqlxError> {
+        let current: Option<i64> =
+            sqlx::query_scalar("SELECT `value` FROM `versions` WHERE `key` = 'CURRENT'")
+                .fetch_optional(&mut *conn)
+                .await
+                .ok()
+                .flatten();
+
+        if let Some(current) = current {
+            return Ok(current);
+        }
+
+        sqlx::query_scalar("SELECT `value` FROM `preferences` WHERE `key` = 'DB_VERSION'")
+            .fetch_one(&mut *conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Return true if the crate can read and write a library of this version without migrating it
+    pub fn is_compatible_version(version: i64) -> bool {
+        version >= LIBRARY_VERSION && version / 100 == LIBRARY_VERSION / 100
+    }
+
+    /// Open the library, and migrate it to [LIBRARY_VERSION] if it is older
+    pub async fn open_and_migrate(path: PathBuf) -> Result<Self, LibraryMigrationError> {
+        let lib = Self::open_library(path).context(OpenSnafu)?;
+        lib.migrate().await?;
+        Ok(lib)
+    }
+
+    /// Upgrade the library to [LIBRARY_VERSION].
+    ///
+    /// A backup of the database is taken before any change is made.
+    /// All the steps are applied in a single transaction, so a failed migration leaves the library untouched
+    pub async fn migrate(&self) -> Result<MigrationReport, LibraryMigrationError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let from_version = Self::get_version(conn).await.context(SqlSnafu)?;
+
+        if Self::is_compatible_version(from_version) {
+            return Ok(MigrationReport {
+                from_version,
+                to_version: from_version,
+                steps: Vec::new(),
+                backup: None,
+            });
+        }
+
+        if from_version > LIBRARY_VERSION {
+            return LibraryTooRecentSnafu {
+                lib_version: from_version,
+            }
+            .fail();
+        }
+
+        if from_version < MIN_MIGRATABLE_VERSION {
+            return LibraryTooOldSnafu {
+                lib_version: from_version,
+            }
+            .fail();
+        }
+
+        let backup = self.backup_before_migration(conn).await?;
+
+        let steps = MigrationStep::steps_for(from_version);
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        for step in &steps {
+            info!("Applying migration step {step:?}");
+            step.apply(&mut trans).await.context(SqlSnafu)?;
+        }
+
+        set_version(&mut trans, from_version)
+            .await
+            .context(SqlSnafu)?;
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        Ok(MigrationReport {
+            from_version,
+            to_version: LIBRARY_VERSION,
+            steps,
+            backup,
+        })
+    }
+
+    /// Save a copy of the database in the backup folder of the library.
+    ///
+    /// In memory libraries have nowhere to save the backup, so they are skipped
+    async fn backup_before_migration(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Option<PathBuf>, LibraryMigrationError> {
+        if self.path.as_os_str().is_empty() {
+            return Ok(None);
+        }
+
+        let backup = self.write_backup(conn).await.context(BackupSnafu)?;
+        Ok(Some(backup.path))
+    }
+}
+
+/// Write the new version in the database
+async fn set_version(
+    conn: &mut sqlx::SqliteConnection,
+    from_version: i64,
+) -> Result<(), SqlxError> {
+    sqlx::raw_sql(
+        r#"CREATE TABLE IF NOT EXISTS versions (
+	"key" VARCHAR NOT NULL,
+	value INTEGER NOT NULL,
+	PRIMARY KEY ("key")
+)"#,
+    )
+    .execute(&mut *conn)
+    .await
+    .context(SqlxSnafu)?;
+
+    sqlx::query("INSERT OR IGNORE INTO `versions` (`key`, `value`) VALUES ('INITIAL', $1)")
+        .bind(from_version)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+    sqlx::query("INSERT OR REPLACE INTO `versions` (`key`, `value`) VALUES ('CURRENT', $1)")
+        .bind(LIBRARY_VERSION)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+    // Keep the legacy key in sync for older versions of the app
+    sqlx::query("UPDATE `preferences` SET `value` = $1 WHERE `key` = 'DB_VERSION'")
+        .bind(LIBRARY_VERSION)
+        .execute(&mut *conn)
+        .await
+        .context(SqlxSnafu)?;
+
+    Ok(())
+}
+
+/// The changes done by [Library::migrate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: i64,
+    pub to_version: i64,
+
+    /// The steps that got applied
+    pub steps: Vec<MigrationStep>,
+
+    /// The path of the backup taken before migrating
+    pub backup: Option<PathBuf>,
+}
+
+/// Error for [Library::migrate]
+#[derive(Debug, Snafu)]
+pub enum LibraryMigrationError {
+    #[snafu(display("Error while opening the library"))]
+    OpenError {
+        #[snafu(backtrace)]
+        source: LibraryOpenError,
+    },
+
+    #[snafu(display(
+        "The library version ({lib_version}) is more recent than the version of the crate ({LIBRARY_VERSION}). Please update the crate"
+    ))]
+    LibraryTooRecent {
+        lib_version: i64,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "The library version ({lib_version}) is too old to be migrated. Please open it with the TagStudio app first"
+    ))]
+    LibraryTooOld {
+        lib_version: i64,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't backup the library before migrating"))]
+    BackupError {
+        #[snafu(backtrace)]
+        source: BackupError,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::library::LibraryTryOpenError;
+    use crate::models::library::migration::LIBRARY_VERSION;
+    use crate::models::library::migration::steps::MigrationStep;
+
+    #[tokio::test]
+    pub async fn migrate_db102_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        // Downgrade the library to version 102
+        sqlx::raw_sql(
+            "ALTER TABLE `tags` DROP COLUMN `is_hidden`;
+            UPDATE `versions` SET `value` = 102 WHERE `key` = 'CURRENT';",
+        )
+        .execute(&mut *lib.db.get().await.unwrap())
+        .await
+        .unwrap();
+        drop(lib);
+
+        assert!(matches!(
+            Library::try_open_library(dir.path().to_path_buf()).await,
+            Err(LibraryTryOpenError::IncompatibleVersion { .. })
+        ));
+
+        let lib = Library::open_library(dir.path().to_path_buf()).unwrap();
+        let report = lib.migrate().await.unwrap();
+
+        assert_eq!(report.from_version, 102);
+        assert_eq!(report.to_version, LIBRARY_VERSION);
+        assert_eq!(report.steps, vec![MigrationStep::Db103SchemaChanges]);
+        assert!(report.backup.unwrap().exists());
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        assert_eq!(Library::get_version(conn).await.unwrap(), LIBRARY_VERSION);
+        assert!(!Tag::find_by_id(conn, 0).await.unwrap().unwrap().is_hidden);
+
+        // Migrating again is a no-op
+        assert!(lib.migrate().await.unwrap().steps.is_empty());
+    }
+}
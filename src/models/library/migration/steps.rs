@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use futures::TryStreamExt as _;
+use snafu::ResultExt;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// A single upgrade step of a library's database.
+///
+/// Those mirror the migrations applied by the TagStudio app when opening an older library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStep {
+    /// Add the `color_border` column to the tag colors
+    Db8SchemaChanges,
+
+    /// Add the `filename` column to the entries
+    Db9SchemaChanges,
+
+    /// Add the `is_hidden` column to the tags
+    Db103SchemaChanges,
+
+    /// Repair the tags of version 6 libraries that are disambiguated by a deleted tag
+    Db6Repairs,
+
+    /// Add the neon tag colors
+    Db8DefaultData,
+
+    /// Fill the `filename` column of the entries from their path
+    Db9FilenamePopulation,
+
+    /// Swap the `parent_id` and `child_id` of the tag parents, as they were inverted before version 100
+    Db100ParentRepairs,
+
+    /// Remove the tag parents that refer to deleted tags, or to themselves
+    Db102Repairs,
+}
+
+impl MigrationStep {
+    /// Get the steps needed to upgrade a library of the given version, in the order they need to be applied.
+    ///
+    /// Like in the app, schema changes are all applied before the data changes
+    pub fn steps_for(version: i64) -> Vec<Self> {
+        let mut steps = Vec::new();
+
+        if version < 8 {
+            steps.push(Self::Db8SchemaChanges);
+        }
+        if version < 9 {
+            steps.push(Self::Db9SchemaChanges);
+        }
+        if version < 103 {
+            steps.push(Self::Db103SchemaChanges);
+        }
+        if version == 6 {
+            steps.push(Self::Db6Repairs);
+        }
+        if (6..8).contains(&version) {
+            steps.push(Self::Db8DefaultData);
+        }
+        if version < 9 {
+            steps.push(Self::Db9FilenamePopulation);
+        }
+        if version < 100 {
+            steps.push(Self::Db100ParentRepairs);
+        }
+        if version < 102 {
+            steps.push(Self::Db102Repairs);
+        }
+
+        steps
+    }
+
+    /// Apply the step on the database
+    pub async fn apply(self, conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+        match self {
+            Self::Db8SchemaChanges => {
+                sqlx::raw_sql(
+                    "ALTER TABLE `tag_colors` ADD COLUMN `color_border` BOOLEAN NOT NULL DEFAULT 0",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+            Self::Db9SchemaChanges => {
+                sqlx::raw_sql(
+                    "ALTER TABLE `entries` ADD COLUMN `filename` VARCHAR NOT NULL DEFAULT ''",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+            Self::Db103SchemaChanges => {
+                sqlx::raw_sql(
+                    "ALTER TABLE `tags` ADD COLUMN `is_hidden` BOOLEAN NOT NULL DEFAULT 0",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+            Self::Db6Repairs => {
+                sqlx::raw_sql(
+                    "UPDATE `tags` SET `disambiguation_id` = NULL
+                    WHERE `disambiguation_id` NOT IN (SELECT `id` FROM `tags`)",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+            Self::Db8DefaultData => {
+                sqlx::raw_sql(DB8_NEON_COLORS)
+                    .execute(conn)
+                    .await
+                    .context(SqlxSnafu)?;
+            }
+            Self::Db9FilenamePopulation => populate_filenames(conn).await?,
+            Self::Db100ParentRepairs => {
+                sqlx::raw_sql(
+                    "UPDATE `tag_parents` SET `parent_id` = `child_id`, `child_id` = `parent_id`",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+            Self::Db102Repairs => {
+                sqlx::raw_sql(
+                    "DELETE FROM `tag_parents`
+                    WHERE `parent_id` = `child_id`
+                        OR `parent_id` NOT IN (SELECT `id` FROM `tags`)
+                        OR `child_id` NOT IN (SELECT `id` FROM `tags`)",
+                )
+                .execute(conn)
+                .await
+                .context(SqlxSnafu)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Set the filename of all the entries from the last component of their path
+async fn populate_filenames(conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
+    let entries: Vec<(i64, String)> = sqlx::query_as("SELECT `id`, `path` FROM `entries`")
+        .fetch(&mut *conn)
+        .try_collect()
+        .await
+        .context(SqlxSnafu)?;
+
+    for (id, path) in entries {
+        let filename = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        sqlx::query("UPDATE `entries` SET `filename` = $1 WHERE `id` = $2")
+            .bind(filename)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)?;
+    }
+
+    Ok(())
+}
+
+const DB8_NEON_COLORS: &str = r#"
+INSERT OR IGNORE INTO namespaces VALUES('tagstudio-neon','TagStudio Neon');
+INSERT OR IGNORE INTO tag_colors VALUES('neon-red','tagstudio-neon','Neon Red','#180607','#E22C3C',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-red-orange','tagstudio-neon','Neon Red Orange','#220905','#E83726',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-orange','tagstudio-neon','Neon Orange','#1F0D05','#ED6022',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-amber','tagstudio-neon','Neon Amber','#251507','#FA9A2C',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-yellow','tagstudio-neon','Neon Yellow','#2B1C0B','#FFD63D',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-lime','tagstudio-neon','Neon Lime','#1B220C','#92E649',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-green','tagstudio-neon','Neon Green','#091610','#45D649',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-teal','tagstudio-neon','Neon Teal','#09191D','#22D589',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-cyan','tagstudio-neon','Neon Cyan','#0B191C','#3DDBDB',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-blue','tagstudio-neon','Neon Blue','#09101C','#3B87F0',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-indigo','tagstudio-neon','Neon Indigo','#150B24','#874FF5',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-purple','tagstudio-neon','Neon Purple','#1E0B26','#BB4FF0',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-pink','tagstudio-neon','Neon Pink','#210E15','#FF62AF',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-magenta','tagstudio-neon','Neon Magenta','#220A13','#F64680',1);
+INSERT OR IGNORE INTO tag_colors VALUES('neon-white','tagstudio-neon','Neon White','#131315','#F2F1F8',1);
+"#;
+
+#[cfg(test)]
+mod tests {
+    use crate::models::library::migration::steps::MigrationStep;
+
+    #[test]
+    pub fn steps_for_test() {
+        assert_eq!(MigrationStep::steps_for(103), vec![]);
+        assert_eq!(
+            MigrationStep::steps_for(102),
+            vec![MigrationStep::Db103SchemaChanges]
+        );
+        assert_eq!(
+            MigrationStep::steps_for(9),
+            vec![
+                MigrationStep::Db103SchemaChanges,
+                MigrationStep::Db100ParentRepairs,
+                MigrationStep::Db102Repairs,
+            ]
+        );
+        assert_eq!(
+            MigrationStep::steps_for(6),
+            vec![
+                MigrationStep::Db8SchemaChanges,
+                MigrationStep::Db9SchemaChanges,
+                MigrationStep::Db103SchemaChanges,
+                MigrationStep::Db6Repairs,
+                MigrationStep::Db8DefaultData,
+                MigrationStep::Db9FilenamePopulation,
+                MigrationStep::Db100ParentRepairs,
+                MigrationStep::Db102Repairs,
+            ]
+        );
+    }
+}
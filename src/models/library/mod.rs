@@ -1,4 +1,5 @@
 use core::str::FromStr as _;
+use core::sync::atomic::AtomicBool;
 use core::time::Duration;
 use std::backtrace::Backtrace;
 use std::path::Path;
@@ -15,11 +16,35 @@ use crate::client::conn_pool::PoolManager;
 use crate::client::conn_pool::TSConnectionPool;
 use crate::models::errors::sqlx_error::SqlxError;
 use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::migration::LIBRARY_VERSION;
+
+pub mod backup;
+pub mod capabilities;
+pub mod create;
+#[cfg(feature = "fs")]
+pub mod duplicates;
+#[cfg(feature = "fs")]
+pub mod hash_cache;
+pub mod ignore_rules;
+pub mod journal;
+pub mod migration;
+#[cfg(feature = "fs")]
+pub mod move_folder;
+#[cfg(feature = "fs")]
+pub mod refresh;
+pub(crate) mod schema;
+#[cfg(feature = "fs")]
+pub mod unlinked;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 /// A struct representing a TagStudio library.
 pub struct Library {
     pub path: PathBuf,
     pub db: TSConnectionPool,
+
+    /// Whether to take a backup before destructive bulk operations. See [Library::set_auto_backup]
+    auto_backup: AtomicBool,
 }
 
 impl Library {
@@ -27,18 +52,21 @@ impl Library {
     pub async fn try_open_library(path: PathBuf) -> Result<Self, LibraryTryOpenError> {
         let lib = Self::open_library(path).context(OpenSnafu)?;
 
-        let res = sqlx::query_scalar("SELECT VALUE FROM `versions` WHERE `key` = 'CURRENT'")
-            .fetch_one(&mut *lib.db.get().await.context(SqlPoolSnafu)?)
-            .await;
+        let conn = &mut *lib.db.get().await.context(SqlPoolSnafu)?;
+        let version = Self::get_version(conn).await.unwrap_or(100);
 
-        let version = res.unwrap_or(100);
+        if Self::is_compatible_version(version) {
+            // Fill the schema cache of the connection, and check that the schema can be read
+            lib.schema(conn)
+                .await
+                .context(SqlSnafu)
+                .context(OpenSnafu)?;
 
-        if version >= 103 && version % 100 == 1 {
             Ok(lib)
         } else {
             Err(LibraryTryOpenError::IncompatibleVersion {
                 lib_version: version,
-                allowed_version: 103,
+                allowed_version: LIBRARY_VERSION,
                 backtrace: Backtrace::capture(),
             })
         }
@@ -65,6 +93,7 @@ impl Library {
         Ok(Self {
             path: root,
             db: pool,
+            auto_backup: Default::default(),
         })
     }
 
@@ -82,6 +111,7 @@ impl Library {
         Ok(Self {
             path: root,
             db: pool,
+            auto_backup: Default::default(),
         })
     }
 
@@ -125,6 +155,7 @@ impl Library {
         Ok(Self {
             path: "".into(),
             db: pool,
+            auto_backup: Default::default(),
         })
     }
 }
@@ -157,7 +188,7 @@ pub enum LibraryTryOpenError {
     },
 
     #[snafu(display(
-        "The current library version in incompatible with the version of the crate ({lib_version} vs {allowed_version}). Please upgrade the library to version {allowed_version}, or update the crate to version {lib_version}"
+        "The current library version in incompatible with the version of the crate ({lib_version} vs {allowed_version}). Please upgrade the library to version {allowed_version} with `Library::migrate`, or update the crate to version {lib_version}"
     ))]
     IncompatibleVersion {
         lib_version: i64,
@@ -0,0 +1,274 @@
+use std::backtrace::Backtrace;
+use std::path::Path;
+use std::path::PathBuf;
+
+use snafu::OptionExt as _;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use sqlx::AssertSqlSafe;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::entry::delete::FileDeletion;
+use crate::models::entry::fs::merge_entry::MergeEntryError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::models::library::Library;
+use crate::models::library::backup::BackupError;
+use crate::models::library::refresh::relative_path_to_string;
+
+impl Library {
+    /// Move a folder of the library, and update the path of all the entries inside it. This takes in full paths.
+    ///
+    /// The folder is renamed once on disk, then all the entries are updated in a single transaction.
+    /// If the database can't be updated, the folder is moved back.
+    ///
+    /// The destination must not exist on disk, but unlinked entries may still point inside it.
+    /// Like [Entry::move_or_merge_same], the moved entries are then merged into them.
+    /// A backup is taken first if [Library::set_auto_backup] is enabled
+    pub async fn move_folder(
+        &self,
+        from: &Path,
+        to: &Path,
+    ) -> Result<MoveFolderReport, MoveFolderError> {
+        let relative_from = self.relative_folder_path(from)?;
+        let relative_to = self.relative_folder_path(to)?;
+
+        if to.try_exists().context(IoSnafu { path: to })? {
+            return DestinationOccupiedSnafu { path: to }.fail();
+        }
+
+        self.auto_backup().await.context(BackupSnafu)?;
+
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let root_folder = self.get_root_folder(conn).await.context(SqlSnafu)?;
+
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).context(IoSnafu { path: parent })?;
+        }
+
+        debug!("Moving `{}` to `{}`", from.display(), to.display());
+        std::fs::rename(from, to).context(IoSnafu { path: from })?;
+
+        match move_folder_entries(
+            conn,
+            &relative_from,
+            &relative_to,
+            root_folder.as_ref(),
+            &self.path,
+        )
+        .await
+        {
+            Ok(report) => Ok(report),
+            Err(err) => {
+                warn!("Couldn't update the entries. Moving the folder back");
+                std::fs::rename(to, from).context(IoSnafu { path: to })?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Get the path of a folder relative to the library root, with a trailing `/`
+    fn relative_folder_path(&self, path: &Path) -> Result<String, MoveFolderError> {
+        let relative = path
+            .strip_prefix(&self.path)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .context(NotInLibrarySnafu { path })?;
+
+        Ok(format!("{}/", relative_path_to_string(relative)))
+    }
+}
+
+/// Rewrite the paths of the entries of a folder that got moved on disk. Both paths are relative, with a trailing `/`
+pub(crate) async fn move_folder_entries(
+    conn: &mut sqlx::SqliteConnection,
+    from: &str,
+    to: &str,
+    root_folder: Option<&Folder>,
+    library_root: &Path,
+) -> Result<MoveFolderReport, MoveFolderError> {
+    let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+    let folder_condition = match root_folder {
+        Some(folder) => format!("AND (`folder_id` IS NULL OR `folder_id` = {})", folder.id),
+        None => String::new(),
+    };
+
+    // Merge the entries that would take the path of an existing one
+    let conflicts: Vec<Entry> = sqlx::query_as(AssertSqlSafe(format!(
+        "SELECT * FROM `entries`
+        WHERE substr(`path`, 1, length($1)) = $1 {folder_condition}
+            AND ($2 || substr(`path`, length($1) + 1)) IN (SELECT `path` FROM `entries`)"
+    )))
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *trans)
+    .await
+    .context(SqlxSnafu)
+    .context(SqlSnafu)?;
+
+    let merged = conflicts.len();
+    for moved in conflicts {
+        let new_path = format!("{to}{}", &moved.path[from.len()..]);
+        let Some(existing) = Entry::find_by_path(&mut trans, &new_path)
+            .await
+            .context(SqlSnafu)?
+            .pop()
+        else {
+            continue;
+        };
+
+        debug!("Merging `{}` into `{}`", moved.path, existing.path);
+        // Only the entry gets removed. The file is kept, even if something is at the old path again
+        existing
+            .merge_entry_with_deletion(&mut trans, moved, library_root, FileDeletion::Keep)
+            .await
+            .context(MergeSnafu)?;
+    }
+
+    let moved = sqlx::query(AssertSqlSafe(format!(
+        "UPDATE `entries` SET `path` = $2 || substr(`path`, length($1) + 1)
+        WHERE substr(`path`, 1, length($1)) = $1 {folder_condition}"
+    )))
+    .bind(from)
+    .bind(to)
+    .execute(&mut *trans)
+    .await
+    .context(SqlxSnafu)
+    .context(SqlSnafu)?
+    .rows_affected();
+
+    trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+    Ok(MoveFolderReport { moved, merged })
+}
+
+/// The result of [Library::move_folder]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveFolderReport {
+    /// The number of entries that got their path updated
+    pub moved: u64,
+
+    /// The number of entries that got merged into an existing entry at the destination
+    pub merged: usize,
+}
+
+/// Error for [Library::move_folder]
+#[derive(Debug, Snafu)]
+pub enum MoveFolderError {
+    #[snafu(display("`{}` isn't a folder of the library", path.display()))]
+    NotInLibrary { path: PathBuf, backtrace: Backtrace },
+
+    #[snafu(display("`{}` already exists", path.display()))]
+    DestinationOccupied { path: PathBuf, backtrace: Backtrace },
+
+    #[snafu(display("Couldn't move `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't backup the library"))]
+    BackupError {
+        #[snafu(backtrace)]
+        source: BackupError,
+    },
+
+    #[snafu(display("Couldn't merge an entry with the destination"))]
+    MergeError {
+        #[snafu(backtrace)]
+        source: MergeEntryError,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Entry;
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::library::move_folder::MoveFolderError;
+
+    #[tokio::test]
+    pub async fn move_folder_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let lib = Library::create(root.to_path_buf()).await.unwrap();
+
+        fs::create_dir_all(root.join("cats/black")).unwrap();
+        fs::write(root.join("cats/black/maxwell.png"), "").unwrap();
+        fs::write(root.join("cats/oiia.png"), "").unwrap();
+        fs::write(root.join("cats.png"), "").unwrap();
+        lib.refresh().await.unwrap();
+
+        // An unlinked entry at the destination, with a tag
+        let stale = {
+            let conn = &mut *lib.db.get().await.unwrap();
+            let mut stale = Entry::find_by_path(conn, "cats/oiia.png")
+                .await
+                .unwrap()
+                .pop()
+                .unwrap();
+            stale.path = "memes/oiia.png".to_string();
+            let stale = stale.insert(conn).await.unwrap();
+            let tag = Tag::from("Meme").insert_tag(conn).await.unwrap();
+            stale.add_tag(conn, &tag).await.unwrap();
+            stale
+        };
+
+        let report = lib
+            .move_folder(&root.join("cats"), &root.join("memes"))
+            .await
+            .unwrap();
+        assert_eq!(report.moved, 1);
+        assert_eq!(report.merged, 1);
+        assert!(root.join("memes/black/maxwell.png").exists());
+        assert!(root.join("cats.png").exists());
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        assert_eq!(
+            Entry::find_by_path(conn, "memes/black/maxwell.png")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        let oiia = Entry::find_by_path(conn, "memes/oiia.png")
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(oiia.id, stale.id);
+        assert_eq!(oiia.get_tags(conn).await.unwrap().len(), 1);
+        assert_eq!(
+            Entry::find_by_path(conn, "cats.png").await.unwrap().len(),
+            1
+        );
+
+        // Can't move onto an existing folder
+        fs::create_dir_all(root.join("dogs")).unwrap();
+        assert!(matches!(
+            lib.move_folder(&root.join("memes"), &root.join("dogs"))
+                .await,
+            Err(MoveFolderError::DestinationOccupied { .. })
+        ));
+    }
+}
@@ -0,0 +1,357 @@
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use tracing::debug;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::entry::from_path::EntryFromPathError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::folder::Folder;
+use crate::models::library::Library;
+use crate::models::library::ignore_rules::IGNORE_FILE_PATH;
+use crate::models::library::ignore_rules::IgnoreRules;
+use crate::models::preferences::PreferenceError;
+use crate::models::preferences::Preferences;
+use crate::models::preferences::extension_filter::ExtensionFilter;
+
+/// File and folder names that are never added to a library, whatever the preferences are. Same as the app's global ignore list
+pub const GLOBAL_IGNORE: [&str; 9] = [
+    ".TagStudio",
+    "$RECYCLE.BIN",
+    ".Trashes",
+    ".Trash",
+    "tagstudio_thumbs",
+    ".fseventsd",
+    ".Spotlight-V100",
+    "System Volume Information",
+    ".DS_Store",
+];
+
+impl Library {
+    /// Scan the library root, and add the files that don't have an entry yet.
+    ///
+    /// Like the app's "Refresh Directories", this respects the extension list of the library, the `.ts_ignore` rules, and skip the [GLOBAL_IGNORE] files.
+    /// The new entries are all inserted in a single transaction
+    pub async fn refresh(&self) -> Result<RefreshReport, LibraryRefreshError> {
+        let mut report = RefreshReport::default();
+        let files = self.scan_files(&mut report).await?;
+
+        // Remember the sizes, so the files can still be recognized once they go missing
+        let mut hash_cache = self.load_hash_cache();
+        for (relative_path, metadata) in &files {
+            hash_cache.record(&self.path.join(relative_path), metadata);
+        }
+
+        // Only take a connection once the files are known, so the slow walk doesn't hold it
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let schema = self.schema(conn).await.context(SqlSnafu)?;
+        let root_folder = self.get_root_folder(conn).await.context(SqlSnafu)?;
+        let known_paths = get_known_paths(conn, root_folder.as_ref())
+            .await
+            .context(SqlSnafu)?;
+
+        // Forget the files that are gone, unless an entry may still be relinked to them
+        let scanned = files
+            .iter()
+            .map(|(relative_path, _)| relative_path.as_path())
+            .collect::<HashSet<_>>();
+        hash_cache.retain(|full_path| match full_path.strip_prefix(&self.path) {
+            Ok(relative_path) => {
+                scanned.contains(relative_path)
+                    || known_paths.contains(&relative_path_to_string(relative_path))
+            }
+            // Files of other folders aren't scanned
+            Err(_) => true,
+        });
+
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+        let date_added = Local::now().naive_local();
+
+        for (relative_path, metadata) in files {
+            let path = relative_path_to_string(&relative_path);
+            if known_paths.contains(&path) {
+                continue;
+            }
+
+            debug!("Found new file `{path}`");
+            let mut entry = Entry::from_path(self, &relative_path).context(InvalidPathSnafu)?;
+            entry.date_created = metadata.created().ok().map(to_naive_local);
+            entry.date_modified = metadata.modified().ok().map(to_naive_local);
+            entry.date_added = Some(date_added);
+            entry.folder_id = root_folder.as_ref().map(|folder| folder.id);
+
+            let entry = entry
+                .insert_with_schema(&mut trans, &schema)
+                .await
+                .context(SqlSnafu)?;
+            report.added.push(entry);
+        }
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        let cache_path = self.get_hash_cache_path();
+        hash_cache
+            .save(&cache_path)
+            .context(IoSnafu { path: cache_path })?;
+
+        Ok(report)
+    }
+
+    /// Get the folder of the library root. Returns `None` on libraries without folders
+    pub(crate) async fn get_root_folder(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Option<Folder>, SqlxError> {
+        if !self.schema(conn).await?.has_folders {
+            return Ok(None);
+        }
+
+        Folder::find_by_path(conn, &self.path).await
+    }
+
+    /// Get the paths of all the folders of the library, by id. Empty on libraries without folders
+    pub(crate) async fn get_folder_paths(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<HashMap<i64, PathBuf>, SqlxError> {
+        Folder::find_all_paths(conn).await
+    }
+
+    /// Get the full path of an entry, using the paths from [Library::get_folder_paths]
+    pub(crate) fn resolve_entry_path(
+        &self,
+        folders: &HashMap<i64, PathBuf>,
+        entry: &Entry,
+    ) -> PathBuf {
+        entry.get_full_path_in(folders, &self.path)
+    }
+
+    /// Walk the library root, and return the relative paths of the files allowed by the preferences and the ignore rules.
+    ///
+    /// The walk runs on a blocking thread, and no connection is held during it
+    pub(crate) async fn scan_files(
+        &self,
+        report: &mut RefreshReport,
+    ) -> Result<Vec<(PathBuf, Metadata)>, LibraryRefreshError> {
+        let filter = {
+            let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+            Preferences::get_extension_filter(conn)
+                .await
+                .context(PreferenceSnafu)?
+        };
+
+        let root = self.path.clone();
+        let (files, scan_report) = tokio::task::spawn_blocking(move || {
+            let ignore_path = root.join(IGNORE_FILE_PATH);
+            let ignore_rules =
+                IgnoreRules::from_file(&ignore_path).context(IoSnafu { path: ignore_path })?;
+
+            let mut scan_report = RefreshReport::default();
+            let files = scan_folder(&root, &root, &filter, &ignore_rules, &mut scan_report)?;
+            Ok::<_, LibraryRefreshError>((files, scan_report))
+        })
+        .await
+        .context(ScanSnafu)??;
+
+        report.scanned += scan_report.scanned;
+        report.skipped += scan_report.skipped;
+        Ok(files)
+    }
+}
+
+/// Get the paths of the entries of the folder
+pub(crate) async fn get_known_paths(
+    conn: &mut sqlx::SqliteConnection,
+    folder: Option<&Folder>,
+) -> Result<HashSet<String>, SqlxError> {
+    let paths: Vec<String> = match folder {
+        Some(folder) => sqlx::query_scalar(
+            "SELECT `path` FROM `entries` WHERE `folder_id` = $1 OR `folder_id` IS NULL",
+        )
+        .bind(folder.id)
+        .fetch_all(conn)
+        .await
+        .context(SqlxSnafu)?,
+        None => sqlx::query_scalar("SELECT `path` FROM `entries`")
+            .fetch_all(conn)
+            .await
+            .context(SqlxSnafu)?,
+    };
+
+    Ok(paths.into_iter().collect())
+}
+
+/// Walk a folder of the library, and return the paths of the files that can be added, relative to the library root
+pub(crate) fn scan_folder(
+    root: &Path,
+    folder: &Path,
+    filter: &ExtensionFilter,
+    ignore_rules: &IgnoreRules,
+    report: &mut RefreshReport,
+) -> Result<Vec<(PathBuf, Metadata)>, LibraryRefreshError> {
+    let mut files = Vec::new();
+    let mut folders = vec![folder.to_path_buf()];
+
+    while let Some(folder) = folders.pop() {
+        for dir_entry in std::fs::read_dir(&folder).context(IoSnafu { path: &folder })? {
+            let dir_entry = dir_entry.context(IoSnafu { path: &folder })?;
+            let path = dir_entry.path();
+
+            let name = dir_entry.file_name();
+            if GLOBAL_IGNORE.iter().any(|ignored| name == **ignored) {
+                continue;
+            }
+
+            let relative_path = path
+                .strip_prefix(root)
+                .expect("The scanned file should be in the root")
+                .to_path_buf();
+            let relative_path_str = relative_path_to_string(&relative_path);
+
+            // Don't follow symlinks to folders, as they could loop back
+            let file_type = dir_entry.file_type().context(IoSnafu { path: &path })?;
+            if file_type.is_dir() {
+                if !ignore_rules.is_ignored_path(&relative_path_str, true) {
+                    folders.push(path);
+                }
+                continue;
+            }
+
+            let metadata = std::fs::metadata(&path).context(IoSnafu { path: &path })?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            report.scanned += 1;
+            if !filter.is_allowed_path(&path)
+                || ignore_rules.is_ignored_path(&relative_path_str, false)
+            {
+                report.skipped += 1;
+                continue;
+            }
+
+            files.push((relative_path, metadata));
+        }
+    }
+
+    // Keep the insertion order stable between runs
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// Entry paths always use `/` as separator, like the app
+pub(crate) fn relative_path_to_string(path: &Path) -> String {
+    path.components()
+        .map(|comp| comp.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(crate) fn to_naive_local(time: std::time::SystemTime) -> NaiveDateTime {
+    DateTime::<Local>::from(time).naive_local()
+}
+
+/// The result of [Library::refresh]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// The entries that got added
+    pub added: Vec<Entry>,
+
+    /// The number of files found in the library
+    pub scanned: usize,
+
+    /// The number of files that are excluded by the extension list or the ignore rules
+    pub skipped: usize,
+}
+
+/// Error for [Library::refresh]
+#[derive(Debug, Snafu)]
+pub enum LibraryRefreshError {
+    #[snafu(display("Couldn't read `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The scan of the library panicked"))]
+    ScanError {
+        source: tokio::task::JoinError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Found a file that can't be added"))]
+    InvalidPath { source: EntryFromPathError },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't read the preferences of the library"))]
+    PreferenceError {
+        #[snafu(backtrace)]
+        source: PreferenceError,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Library;
+
+    #[tokio::test]
+    pub async fn refresh_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+
+        fs::create_dir_all(dir.path().join("cats/black")).unwrap();
+        fs::write(dir.path().join("maxwell.PNG"), "").unwrap();
+        fs::write(dir.path().join("cats/black/maxwell.jpg"), "").unwrap();
+        fs::write(dir.path().join("cats/maxwell.json"), "").unwrap();
+        fs::write(dir.path().join(".DS_Store"), "").unwrap();
+        fs::create_dir_all(dir.path().join("private")).unwrap();
+        fs::write(dir.path().join("private/secret.png"), "").unwrap();
+        fs::write(dir.path().join(".TagStudio/.ts_ignore"), "private/\n").unwrap();
+
+        let report = lib.refresh().await.unwrap();
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.skipped, 1);
+
+        let paths = report
+            .added
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["cats/black/maxwell.jpg", "maxwell.PNG"]);
+
+        let maxwell = &report.added[1];
+        assert_eq!(maxwell.filename, "maxwell.PNG");
+        assert_eq!(maxwell.suffix, "png");
+        assert_eq!(maxwell.folder_id, Some(1));
+        assert!(maxwell.date_modified.is_some());
+        assert!(maxwell.date_added.is_some());
+
+        // Nothing new
+        assert!(lib.refresh().await.unwrap().added.is_empty());
+    }
+}
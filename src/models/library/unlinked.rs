@@ -0,0 +1,354 @@
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use futures::TryStreamExt as _;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::Acquire as _;
+use tracing::debug;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+use crate::models::library::refresh::LibraryRefreshError;
+use crate::models::library::refresh::RefreshReport;
+use crate::models::library::refresh::get_known_paths;
+use crate::models::library::refresh::relative_path_to_string;
+use crate::models::library::refresh::to_naive_local;
+
+impl Library {
+    /// Get the entries whose file can't be found on disk
+    pub async fn find_unlinked_entries(&self) -> Result<Vec<Entry>, UnlinkedEntriesError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+
+        let folders = self.get_folder_paths(conn).await.context(SqlSnafu)?;
+
+        Entry::stream_entries(conn)
+            .try_filter(|entry| {
+                let full_path = self.resolve_entry_path(&folders, entry);
+
+                // Only report the files that are surely missing
+                futures::future::ready(matches!(full_path.try_exists(), Ok(false)))
+            })
+            .try_collect()
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)
+    }
+
+    /// Search the library for the files of the unlinked entries, like the app's "Fix Unlinked Entries".
+    ///
+    /// The candidates are files without an entry that have the same filename, and the same size as the last one recorded by [Library::refresh].
+    /// Entries whose size was never recorded are only matched by filename.
+    /// If only one candidate is found, the entry is relinked to it. Otherwise the entry is returned for review.
+    /// Ambiguous entries can be relinked by hand with [Library::relink_entry]
+    pub async fn relink_unlinked_entries(
+        &self,
+        options: RelinkOptions,
+    ) -> Result<RelinkReport, UnlinkedEntriesError> {
+        let unlinked = self.find_unlinked_entries().await?;
+        let mut report = RelinkReport::default();
+        if unlinked.is_empty() {
+            return Ok(report);
+        }
+
+        let files = self
+            .scan_files(&mut RefreshReport::default())
+            .await
+            .context(ScanSnafu)?;
+
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let schema = self.schema(conn).await.context(SqlSnafu)?;
+        let root_folder = self.get_root_folder(conn).await.context(SqlSnafu)?;
+        let folders = self.get_folder_paths(conn).await.context(SqlSnafu)?;
+        let mut used_paths = get_known_paths(conn, root_folder.as_ref())
+            .await
+            .context(SqlSnafu)?;
+        let mut hash_cache = self.load_hash_cache();
+
+        // Index the files without entries by filename
+        let mut candidates: HashMap<String, Vec<_>> = HashMap::new();
+        for (path, metadata) in files {
+            let relative_path = relative_path_to_string(&path);
+            if used_paths.contains(&relative_path) {
+                continue;
+            }
+
+            let filename = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            candidates
+                .entry(filename)
+                .or_default()
+                .push((relative_path, metadata));
+        }
+
+        let mut trans = conn.begin().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        for mut entry in unlinked {
+            let entry_path = self.resolve_entry_path(&folders, &entry);
+
+            // The last known content of the file, if it got hashed before going missing
+            let known_size = hash_cache.get_size(&entry_path);
+            let known_hash = hash_cache.get_cached_hash(&entry_path).map(str::to_string);
+
+            let mut matches = candidates
+                .get(&entry.filename)
+                .into_iter()
+                .flatten()
+                .filter(|(path, _)| !used_paths.contains(path))
+                .filter(|(_, metadata)| known_size.is_none_or(|size| metadata.len() == size))
+                .filter(|(_, metadata)| {
+                    !options.match_modified_date
+                        || entry.date_modified.is_none_or(|date_modified| {
+                            metadata.modified().is_ok_and(|modified| {
+                                to_naive_local(modified).and_utc().timestamp()
+                                    == date_modified.and_utc().timestamp()
+                            })
+                        })
+                })
+                .filter(|(path, _)| {
+                    let Some(hash) = known_hash.as_ref().filter(|_| options.match_content) else {
+                        return true;
+                    };
+
+                    hash_cache
+                        .get_hash(&self.path.join(path))
+                        .is_ok_and(|candidate_hash| candidate_hash == *hash)
+                })
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>();
+
+            match matches.len() {
+                0 => report.missing.push(entry),
+                1 => {
+                    let new_path = matches.pop().expect("There should be one match");
+                    debug!("Relinking `{}` to `{new_path}`", entry.path);
+
+                    entry.path = new_path.clone();
+                    if let Some(folder) = &root_folder {
+                        entry.folder_id = Some(folder.id);
+                    }
+                    entry
+                        .save_with_schema(&mut trans, &schema)
+                        .await
+                        .context(SqlSnafu)?;
+
+                    if let Ok(metadata) = std::fs::metadata(self.path.join(&new_path)) {
+                        hash_cache.record(&self.path.join(&new_path), &metadata);
+                    }
+                    used_paths.insert(new_path);
+                    report.relinked.push(entry);
+                }
+                _ => report.ambiguous.push(AmbiguousRelink {
+                    entry,
+                    candidates: matches,
+                }),
+            }
+        }
+
+        trans.commit().await.context(SqlxSnafu).context(SqlSnafu)?;
+
+        let cache_path = self.get_hash_cache_path();
+        hash_cache
+            .save(&cache_path)
+            .context(HashCacheSnafu { path: cache_path })?;
+
+        Ok(report)
+    }
+
+    /// Point the entry to another file of the library with [Entry::relink].
+    ///
+    /// The size of the new file is recorded in the [HashCache](crate::models::library::hash_cache::HashCache), so the entry can still be relinked if the file goes missing again
+    pub async fn relink_entry(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        entry: &mut Entry,
+        new_path: &str,
+    ) -> Result<(), UnlinkedEntriesError> {
+        entry.relink(conn, new_path).await.context(SqlSnafu)?;
+
+        let full_path = entry
+            .resolve_full_path(conn, &self.path)
+            .await
+            .context(SqlSnafu)?;
+        let Ok(metadata) = std::fs::metadata(&full_path) else {
+            return Ok(());
+        };
+
+        let mut hash_cache = self.load_hash_cache();
+        hash_cache.record(&full_path, &metadata);
+
+        let cache_path = self.get_hash_cache_path();
+        hash_cache
+            .save(&cache_path)
+            .context(HashCacheSnafu { path: cache_path })
+    }
+}
+
+impl Entry {
+    /// Point the entry to another file of the library, without touching the filesystem.
+    ///
+    /// The path is relative to the folder of the entry
+    pub async fn relink(
+        &mut self,
+        conn: &mut sqlx::SqliteConnection,
+        new_path: &str,
+    ) -> Result<(), SqlxError> {
+        self.path = new_path.to_string();
+        if let Some(filename) = self.get_filename() {
+            self.filename = filename.to_string_lossy().to_string();
+        }
+        self.suffix = self
+            .get_relative_path()
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        self.save(conn).await
+    }
+}
+
+/// Options for [Library::relink_unlinked_entries]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RelinkOptions {
+    /// Only consider the candidates that have the same modification date as the entry
+    pub match_modified_date: bool,
+
+    /// Only consider the candidates that have the same content hash as the entry's file had.
+    ///
+    /// This needs the file to have been hashed before it went missing, like with [Library::find_duplicates]. Entries without a known hash are only matched by filename and size
+    pub match_content: bool,
+}
+
+/// An unlinked entry with multiple candidates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousRelink {
+    pub entry: Entry,
+
+    /// The paths of the candidates, relative to the library root
+    pub candidates: Vec<String>,
+}
+
+/// The result of [Library::relink_unlinked_entries]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RelinkReport {
+    /// The entries that got relinked, with their new path
+    pub relinked: Vec<Entry>,
+
+    /// The entries with more than one candidate
+    pub ambiguous: Vec<AmbiguousRelink>,
+
+    /// The entries without any candidate
+    pub missing: Vec<Entry>,
+}
+
+/// Error for [Library::find_unlinked_entries] and [Library::relink_unlinked_entries]
+#[derive(Debug, Snafu)]
+pub enum UnlinkedEntriesError {
+    #[snafu(display("Couldn't scan the library"))]
+    ScanError {
+        #[snafu(backtrace)]
+        source: LibraryRefreshError,
+    },
+
+    #[snafu(display("Couldn't write the hash cache at `{}`", path.display()))]
+    HashCacheError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::Library;
+    use crate::models::library::unlinked::RelinkOptions;
+
+    #[tokio::test]
+    pub async fn relink_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let lib = Library::create(root.to_path_buf()).await.unwrap();
+
+        fs::create_dir_all(root.join("cats")).unwrap();
+        fs::write(root.join("cats/maxwell.png"), "").unwrap();
+        fs::write(root.join("doge.png"), "wow").unwrap();
+        fs::write(root.join("oiia.png"), "").unwrap();
+        lib.refresh().await.unwrap();
+        assert!(lib.find_unlinked_entries().await.unwrap().is_empty());
+
+        // Moved, deleted, and moved with copies
+        fs::create_dir_all(root.join("memes/old")).unwrap();
+        fs::rename(
+            root.join("cats/maxwell.png"),
+            root.join("memes/maxwell.png"),
+        )
+        .unwrap();
+        fs::remove_file(root.join("doge.png")).unwrap();
+        // Same name, but another file
+        fs::write(root.join("memes/doge.png"), "such different").unwrap();
+        fs::rename(root.join("oiia.png"), root.join("memes/oiia.png")).unwrap();
+        fs::write(root.join("memes/old/oiia.png"), "").unwrap();
+        assert_eq!(lib.find_unlinked_entries().await.unwrap().len(), 3);
+
+        let report = lib
+            .relink_unlinked_entries(RelinkOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(report.relinked.len(), 1);
+        assert_eq!(report.relinked[0].path, "memes/maxwell.png");
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].path, "doge.png");
+        assert_eq!(report.ambiguous.len(), 1);
+        assert_eq!(report.ambiguous[0].candidates.len(), 2);
+
+        // Fix the ambiguous one by hand
+        let mut oiia = report.ambiguous[0].entry.clone();
+        lib.relink_entry(
+            &mut lib.db.get().await.unwrap(),
+            &mut oiia,
+            "memes/oiia.png",
+        )
+        .await
+        .unwrap();
+
+        let unlinked = lib.find_unlinked_entries().await.unwrap();
+        assert_eq!(unlinked.len(), 1);
+        assert_eq!(unlinked[0].path, "doge.png");
+
+        // The new paths are recorded, and the missing file is kept until its entry is gone
+        let hash_cache = lib.load_hash_cache();
+        assert_eq!(
+            hash_cache.get_size(&root.join("memes/maxwell.png")),
+            Some(0)
+        );
+        assert_eq!(hash_cache.get_size(&root.join("memes/oiia.png")), Some(0));
+        assert_eq!(hash_cache.get_size(&root.join("doge.png")), Some(3));
+
+        // The paths without file nor entry are pruned by the refresh
+        lib.refresh().await.unwrap();
+        let hash_cache = lib.load_hash_cache();
+        assert_eq!(hash_cache.get_size(&root.join("cats/maxwell.png")), None);
+        assert_eq!(hash_cache.get_size(&root.join("oiia.png")), None);
+        assert_eq!(hash_cache.get_size(&root.join("doge.png")), Some(3));
+    }
+}
@@ -0,0 +1,597 @@
+use std::backtrace::Backtrace;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Local;
+use futures::StreamExt as _;
+use futures::channel::mpsc::UnboundedReceiver;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::event::ModifyKind;
+use notify::event::RenameMode;
+use notify_debouncer_full::DebounceEventResult;
+use notify_debouncer_full::DebouncedEvent;
+use notify_debouncer_full::Debouncer;
+use notify_debouncer_full::RecommendedCache;
+use notify_debouncer_full::new_debouncer;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::AssertSqlSafe;
+use tracing::debug;
+
+use crate::Entry;
+use crate::TSPoolError;
+use crate::models::entry::delete::FileDeletion;
+use crate::models::entry::fs::merge_entry::MergeEntryError;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::Library;
+use crate::models::library::ignore_rules::IGNORE_FILE_PATH;
+use crate::models::library::ignore_rules::IgnoreRules;
+use crate::models::library::move_folder::MoveFolderError;
+use crate::models::library::move_folder::move_folder_entries;
+use crate::models::library::refresh::GLOBAL_IGNORE;
+use crate::models::library::refresh::LibraryRefreshError;
+use crate::models::library::refresh::RefreshReport;
+use crate::models::library::refresh::scan_folder;
+use crate::models::library::unlinked::UnlinkedEntriesError;
+use crate::models::preferences::PreferenceError;
+use crate::models::preferences::Preferences;
+use crate::models::preferences::extension_filter::ExtensionFilter;
+
+/// A watcher on the library root. Stops watching when dropped.
+pub struct LibraryWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+    events: UnboundedReceiver<DebounceEventResult>,
+}
+
+impl LibraryWatcher {
+    /// Wait for the next batch of filesystem events, and apply it to the library.
+    ///
+    /// Returns `None` once the watcher has stopped
+    pub async fn next(
+        &mut self,
+        library: &Library,
+    ) -> Option<Result<Vec<WatchChange>, WatchError>> {
+        let result = self.events.next().await?;
+
+        Some(match result {
+            Ok(events) => library.apply_fs_events(events).await,
+            Err(errors) => WatcherSnafu { errors }.fail(),
+        })
+    }
+}
+
+impl Library {
+    /// Watch the library root for changes. Bursts of events are grouped over the `debounce` duration.
+    ///
+    /// Use [LibraryWatcher::next] to apply the changes to the database
+    pub fn watch(&self, debounce: Duration) -> Result<LibraryWatcher, WatchError> {
+        let (sender, events) = futures::channel::mpsc::unbounded();
+
+        let mut debouncer = new_debouncer(debounce, None, move |result| {
+            // The receiver is only dropped with the debouncer
+            let _ = sender.unbounded_send(result);
+        })
+        .context(NotifySnafu)?;
+        debouncer
+            .watch(&self.path, RecursiveMode::Recursive)
+            .context(NotifySnafu)?;
+
+        Ok(LibraryWatcher {
+            _debouncer: debouncer,
+            events,
+        })
+    }
+
+    /// Apply a batch of filesystem events to the database:
+    /// - Created files are added, with the same rules as [Library::refresh]
+    /// - Renamed files and folders keep their entries, so the tags follow them
+    /// - Deleted files are reported as unlinked. Their entries are kept
+    ///
+    /// Events inside `.TagStudio` are ignored
+    pub async fn apply_fs_events(
+        &self,
+        events: Vec<DebouncedEvent>,
+    ) -> Result<Vec<WatchChange>, WatchError> {
+        let conn = &mut *self.db.get().await.context(SqlPoolSnafu)?;
+        let rules = WatchRules {
+            filter: Preferences::get_extension_filter(conn)
+                .await
+                .context(PreferenceSnafu)?,
+            ignore_rules: self.get_ignore_rules().context(IoSnafu {
+                path: self.path.join(IGNORE_FILE_PATH),
+            })?,
+        };
+
+        let mut changes = Vec::new();
+        for event in events {
+            match (&event.kind, event.paths.as_slice()) {
+                (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) => {
+                    self.on_renamed(conn, &rules, from, to, &mut changes)
+                        .await?
+                }
+                (EventKind::Create(_), paths)
+                | (EventKind::Modify(ModifyKind::Name(RenameMode::To)), paths) => {
+                    for path in paths {
+                        self.on_created(conn, &rules, path, &mut changes).await?;
+                    }
+                }
+                (EventKind::Remove(_), paths)
+                | (EventKind::Modify(ModifyKind::Name(RenameMode::From)), paths) => {
+                    for path in paths {
+                        self.on_removed(conn, path, &mut changes).await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(changes)
+    }
+
+    async fn on_created(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        rules: &WatchRules,
+        path: &Path,
+        changes: &mut Vec<WatchChange>,
+    ) -> Result<(), WatchError> {
+        let is_dir = path.is_dir();
+
+        // Skip `.TagStudio` and the ignored paths before looking inside them
+        if rules.get_entry(self, path, is_dir).is_none() {
+            return Ok(());
+        }
+
+        if !is_dir {
+            return self.add_created_file(conn, rules, path, changes).await;
+        }
+
+        // A whole folder got moved in. Walk it, and add its files with the connection we already hold
+        let root = self.path.clone();
+        let folder = path.to_path_buf();
+        let filter = rules.filter.clone();
+        let ignore_rules = rules.ignore_rules.clone();
+        let files = tokio::task::spawn_blocking(move || {
+            scan_folder(
+                &root,
+                &folder,
+                &filter,
+                &ignore_rules,
+                &mut RefreshReport::default(),
+            )
+        })
+        .await
+        .expect("The scan of the folder shouldn't panic")
+        .context(RefreshSnafu)?;
+
+        for (relative_path, _) in files {
+            self.add_created_file(conn, rules, &self.path.join(relative_path), changes)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Add the entry of a new file, if it isn't in the library yet
+    async fn add_created_file(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        rules: &WatchRules,
+        path: &Path,
+        changes: &mut Vec<WatchChange>,
+    ) -> Result<(), WatchError> {
+        let Some(mut entry) = rules.get_entry(self, path, false) else {
+            return Ok(());
+        };
+        if !path.is_file()
+            || !Entry::find_by_path(conn, &entry.path)
+                .await
+                .context(SqlSnafu)?
+                .is_empty()
+        {
+            return Ok(());
+        }
+
+        (entry.date_created, entry.date_modified) =
+            Entry::read_fs_dates(path).context(IoSnafu { path })?;
+        entry.date_added = Some(Local::now().naive_local());
+        if let Some(folder) = self.get_root_folder(conn).await.context(SqlSnafu)? {
+            entry.folder_id = Some(folder.id);
+        }
+
+        debug!("Adding created file `{}`", entry.path);
+        let entry = entry.insert(conn).await.context(SqlSnafu)?;
+        changes.push(WatchChange::Added(entry));
+        Ok(())
+    }
+
+    async fn on_removed(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        path: &Path,
+        changes: &mut Vec<WatchChange>,
+    ) -> Result<(), WatchError> {
+        let Ok(removed) = Entry::from_path(self, path) else {
+            return Ok(());
+        };
+
+        // The path may be a file or a folder
+        let entries: Vec<Entry> = sqlx::query_as(
+            "SELECT * FROM `entries` WHERE `path` = $1 OR substr(`path`, 1, length($1) + 1) = $1 || '/'",
+        )
+        .bind(&removed.path)
+        .fetch_all(&mut *conn)
+        .await
+        .context(SqlxSnafu)
+        .context(SqlSnafu)?;
+
+        for entry in entries {
+            let full_path = entry
+                .resolve_full_path(conn, &self.path)
+                .await
+                .context(SqlSnafu)?;
+
+            if !full_path.exists() {
+                changes.push(WatchChange::Unlinked(entry));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_renamed(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        rules: &WatchRules,
+        from: &Path,
+        to: &Path,
+        changes: &mut Vec<WatchChange>,
+    ) -> Result<(), WatchError> {
+        let (Ok(old), Ok(new)) = (Entry::from_path(self, from), Entry::from_path(self, to)) else {
+            // Moved in or out of `.TagStudio`, or the library
+            self.on_removed(conn, from, changes).await?;
+            return self.on_created(conn, rules, to, changes).await;
+        };
+
+        if to.is_dir() {
+            let root_folder = self.get_root_folder(conn).await.context(SqlSnafu)?;
+            let report = move_folder_entries(
+                conn,
+                &format!("{}/", old.path),
+                &format!("{}/", new.path),
+                root_folder.as_ref(),
+                &self.path,
+            )
+            .await
+            .context(MoveFolderSnafu)?;
+
+            changes.push(WatchChange::FolderMoved {
+                from: old.path,
+                to: new.path,
+                entries: report.moved,
+            });
+            return Ok(());
+        }
+
+        let Some(mut moved) = Entry::find_by_path(conn, &old.path)
+            .await
+            .context(SqlSnafu)?
+            .pop()
+        else {
+            // The file wasn't in the library yet
+            return self.on_created(conn, rules, to, changes).await;
+        };
+
+        if rules.get_entry(self, to, false).is_none() {
+            // Renamed to an ignored name
+            changes.push(WatchChange::Unlinked(moved));
+            return Ok(());
+        }
+
+        let from_path = moved.path.clone();
+        let entry = match Entry::find_by_path(conn, &new.path)
+            .await
+            .context(SqlSnafu)?
+            .pop()
+        {
+            // Like `move_or_merge_same`, the moved entry is merged into the one at the destination.
+            // The watcher never removes files, as a new file may already be at the old path
+            Some(existing) => {
+                existing
+                    .merge_entry_with_deletion(conn, moved, &self.path, FileDeletion::Keep)
+                    .await
+                    .context(MergeSnafu)?;
+                existing
+            }
+            None => {
+                self.relink_entry(conn, &mut moved, &new.path)
+                    .await
+                    .context(RelinkSnafu)?;
+                moved
+            }
+        };
+
+        debug!("Moved `{from_path}` to `{}`", entry.path);
+        changes.push(WatchChange::Moved {
+            entry,
+            from: from_path,
+        });
+        Ok(())
+    }
+}
+
+/// The rules deciding which files get in the library
+struct WatchRules {
+    filter: ExtensionFilter,
+    ignore_rules: IgnoreRules,
+}
+
+impl WatchRules {
+    /// Get the entry of a path, if the file can be in the library
+    fn get_entry(&self, library: &Library, path: &Path, is_dir: bool) -> Option<Entry> {
+        let entry = Entry::from_path(library, path).ok()?;
+
+        let globally_ignored = entry
+            .path
+            .split('/')
+            .any(|part| GLOBAL_IGNORE.contains(&part));
+        let allowed = is_dir || self.filter.is_allowed_entry(&entry);
+
+        (!globally_ignored && allowed && !self.ignore_rules.is_ignored_path(&entry.path, is_dir))
+            .then_some(entry)
+    }
+}
+
+/// A change made by [Library::apply_fs_events]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchChange {
+    /// A new file got added
+    Added(Entry),
+
+    /// A file got moved or renamed
+    Moved { entry: Entry, from: String },
+
+    /// A folder got moved or renamed
+    FolderMoved {
+        from: String,
+        to: String,
+        entries: u64,
+    },
+
+    /// The file of the entry got deleted, or moved out of the library
+    Unlinked(Entry),
+}
+
+/// Error for [Library::watch]
+#[derive(Debug, Snafu)]
+pub enum WatchError {
+    #[snafu(display("Couldn't watch the library"))]
+    NotifyError {
+        source: notify::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The watcher returned errors: {errors:?}"))]
+    WatcherError { errors: Vec<notify::Error> },
+
+    #[snafu(display("Couldn't read `{}`", path.display()))]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Couldn't read the preferences of the library"))]
+    PreferenceError {
+        #[snafu(backtrace)]
+        source: PreferenceError,
+    },
+
+    #[snafu(display("Couldn't scan a new folder"))]
+    RefreshError {
+        #[snafu(backtrace)]
+        source: LibraryRefreshError,
+    },
+
+    #[snafu(display("Couldn't update the entries of a moved folder"))]
+    MoveFolderError {
+        #[snafu(backtrace)]
+        source: MoveFolderError,
+    },
+
+    #[snafu(display("Couldn't merge a moved entry"))]
+    MergeError {
+        #[snafu(backtrace)]
+        source: MergeEntryError,
+    },
+
+    #[snafu(display("Couldn't relink a moved entry"))]
+    RelinkError {
+        #[snafu(backtrace)]
+        source: UnlinkedEntriesError,
+    },
+
+    #[snafu(display("Sqlite returned an error"))]
+    SqlPoolError {
+        source: TSPoolError,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use notify::Event;
+    use notify::EventKind;
+    use notify::event::CreateKind;
+    use notify::event::ModifyKind;
+    use notify::event::RemoveKind;
+    use notify::event::RenameMode;
+    use notify_debouncer_full::DebouncedEvent;
+
+    use crate::Library;
+    use crate::Tag;
+    use crate::models::library::watch::WatchChange;
+
+    fn event(kind: EventKind, paths: Vec<PathBuf>) -> DebouncedEvent {
+        let mut event = Event::new(kind);
+        event.paths = paths;
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    #[tokio::test]
+    pub async fn apply_fs_events_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = Library::create(root.clone()).await.unwrap();
+
+        // Creation
+        fs::write(root.join("maxwell.png"), "").unwrap();
+        fs::write(root.join(".TagStudio/thumb.png"), "").unwrap();
+        let changes = lib
+            .apply_fs_events(vec![
+                event(
+                    EventKind::Create(CreateKind::File),
+                    vec![root.join("maxwell.png")],
+                ),
+                event(
+                    EventKind::Create(CreateKind::File),
+                    vec![root.join(".TagStudio/thumb.png")],
+                ),
+            ])
+            .await
+            .unwrap();
+        let [WatchChange::Added(maxwell)] = changes.as_slice() else {
+            panic!("Expected one added entry, got {changes:?}");
+        };
+
+        {
+            let conn = &mut *lib.db.get().await.unwrap();
+            let tag = Tag::from("Cat").insert_tag(conn).await.unwrap();
+            maxwell.add_tag(conn, &tag).await.unwrap();
+        }
+
+        // Rename. The tag follows the file
+        fs::create_dir_all(root.join("cats")).unwrap();
+        fs::rename(root.join("maxwell.png"), root.join("cats/maxwell.png")).unwrap();
+        let changes = lib
+            .apply_fs_events(vec![event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                vec![root.join("maxwell.png"), root.join("cats/maxwell.png")],
+            )])
+            .await
+            .unwrap();
+        let [WatchChange::Moved { entry, from }] = changes.as_slice() else {
+            panic!("Expected one moved entry, got {changes:?}");
+        };
+        assert_eq!(from, "maxwell.png");
+        assert_eq!(entry.path, "cats/maxwell.png");
+        assert_eq!(entry.id, maxwell.id);
+
+        // Folder rename
+        fs::rename(root.join("cats"), root.join("memes")).unwrap();
+        let changes = lib
+            .apply_fs_events(vec![event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                vec![root.join("cats"), root.join("memes")],
+            )])
+            .await
+            .unwrap();
+        assert!(matches!(
+            changes.as_slice(),
+            [WatchChange::FolderMoved { entries: 1, .. }]
+        ));
+
+        // Deletion
+        fs::remove_dir_all(root.join("memes")).unwrap();
+        let changes = lib
+            .apply_fs_events(vec![event(
+                EventKind::Remove(RemoveKind::Folder),
+                vec![root.join("memes")],
+            )])
+            .await
+            .unwrap();
+        let [WatchChange::Unlinked(entry)] = changes.as_slice() else {
+            panic!("Expected one unlinked entry, got {changes:?}");
+        };
+        assert_eq!(entry.path, "memes/maxwell.png");
+
+        let conn = &mut *lib.db.get().await.unwrap();
+        assert_eq!(entry.get_tags(conn).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn rename_onto_entry_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let lib = Library::create(root.clone()).await.unwrap();
+
+        fs::write(root.join("maxwell.png"), "old").unwrap();
+        fs::write(root.join("copy.png"), "new").unwrap();
+        lib.refresh().await.unwrap();
+
+        // The copy replaces the original, then gets written again
+        fs::rename(root.join("copy.png"), root.join("maxwell.png")).unwrap();
+        fs::write(root.join("copy.png"), "newer").unwrap();
+        let changes = lib
+            .apply_fs_events(vec![event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                vec![root.join("copy.png"), root.join("maxwell.png")],
+            )])
+            .await
+            .unwrap();
+        let [WatchChange::Moved { entry, from }] = changes.as_slice() else {
+            panic!("Expected one moved entry, got {changes:?}");
+        };
+        assert_eq!(from, "copy.png");
+        assert_eq!(entry.path, "maxwell.png");
+
+        // The file at the old path is left alone
+        assert_eq!(fs::read_to_string(root.join("copy.png")).unwrap(), "newer");
+    }
+
+    #[tokio::test]
+    pub async fn watch_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().canonicalize().unwrap();
+        let root = base.join("library");
+        let lib = Library::create(root.clone()).await.unwrap();
+        let mut watcher = lib.watch(Duration::from_millis(50)).unwrap();
+
+        // A folder moved in from outside the library, and one created in `.TagStudio`
+        fs::create_dir_all(base.join("cats/black")).unwrap();
+        fs::write(base.join("cats/black/maxwell.png"), "").unwrap();
+        fs::rename(base.join("cats"), root.join("cats")).unwrap();
+        fs::create_dir_all(root.join(".TagStudio/thumbs")).unwrap();
+        fs::write(root.join(".TagStudio/thumbs/maxwell.png"), "").unwrap();
+
+        let mut added = Vec::new();
+        while added.is_empty() {
+            let changes = tokio::time::timeout(Duration::from_secs(10), watcher.next(&lib))
+                .await
+                .expect("The watcher should report the moved folder")
+                .unwrap()
+                .unwrap();
+
+            added.extend(changes.into_iter().filter_map(|change| match change {
+                WatchChange::Added(entry) => Some(entry.path),
+                _ => None,
+            }));
+        }
+
+        assert_eq!(added, vec!["cats/black/maxwell.png"]);
+    }
+}
@@ -1,12 +1,14 @@
+pub mod boolean_field;
 pub mod datetime_field;
-pub mod datetime_field_template;
 pub mod entry;
 pub mod errors;
+pub mod folder;
 pub mod library;
 pub mod namespaces;
+pub mod preferences;
 pub mod tag;
 pub mod tag_alias;
 pub mod tag_entry;
 pub mod tag_parent;
 pub mod text_field;
-pub mod text_field_template;
+pub mod value_type;
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use crate::Entry;
+
+/// The extension filter of the library, as configured in the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionFilter {
+    /// The extensions, lowercased and without the leading dot
+    extensions: Vec<String>,
+    is_exclude_list: bool,
+}
+
+impl ExtensionFilter {
+    /// Create a new filter. The extensions may have a leading dot, and are case insensitive
+    pub fn new(extensions: Vec<String>, is_exclude_list: bool) -> Self {
+        Self {
+            extensions: extensions
+                .into_iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            is_exclude_list,
+        }
+    }
+
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    pub fn is_exclude_list(&self) -> bool {
+        self.is_exclude_list
+    }
+
+    /// Return true if files with this extension should be in the library
+    pub fn is_allowed_extension(&self, extension: &str) -> bool {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.extensions.contains(&extension) != self.is_exclude_list
+    }
+
+    /// Return true if the file should be in the library
+    pub fn is_allowed_path(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy())
+            .unwrap_or_default();
+
+        self.is_allowed_extension(&extension)
+    }
+
+    /// Return true if the entry's file should be in the library
+    pub fn is_allowed_entry(&self, entry: &Entry) -> bool {
+        self.is_allowed_path(&entry.get_relative_path())
+    }
+}
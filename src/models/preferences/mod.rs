@@ -0,0 +1,256 @@
+use std::backtrace::Backtrace;
+
+use snafu::OptionExt as _;
+use snafu::ResultExt as _;
+use snafu::Snafu;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::preferences::extension_filter::ExtensionFilter;
+
+pub mod extension_filter;
+
+/// The preference keys known by TagStudio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreferenceKey {
+    /// If true, [PreferenceKey::ExtensionList] is a list of extensions to exclude. Otherwise it's a list of extensions to include
+    IsExcludeList,
+
+    /// The list of extensions used to filter the files of the library
+    ExtensionList,
+
+    /// The number of entries shown per page in the app
+    PageSize,
+
+    /// The legacy version key. Newer libraries use the `versions` table
+    DbVersion,
+}
+
+impl PreferenceKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IsExcludeList => "IS_EXCLUDE_LIST",
+            Self::ExtensionList => "EXTENSION_LIST",
+            Self::PageSize => "PAGE_SIZE",
+            Self::DbVersion => "DB_VERSION",
+        }
+    }
+}
+
+/// Access to the `preferences` table of the library.
+///
+/// Values are stored as JSON. The typed getters return the app's default value if the key is missing
+pub struct Preferences;
+
+impl Preferences {
+    /// The default value of [PreferenceKey::IsExcludeList] in the app
+    pub const DEFAULT_IS_EXCLUDE_LIST: bool = true;
+
+    /// The default value of [PreferenceKey::PageSize] in the app
+    pub const DEFAULT_PAGE_SIZE: i64 = 500;
+
+    /// The default value of [PreferenceKey::ExtensionList] in the app
+    pub const DEFAULT_EXTENSION_LIST: [&str; 3] = [".json", ".xmp", ".aae"];
+
+    /// Get the raw JSON value of a preference
+    pub async fn get_raw(
+        conn: &mut sqlx::SqliteConnection,
+        key: &str,
+    ) -> Result<Option<serde_json::Value>, PreferenceError> {
+        // The app writes JSON text, but integers may be stored as is. Casting gives back the JSON representation for both
+        let value: Option<String> =
+            sqlx::query_scalar("SELECT CAST(`value` AS TEXT) FROM `preferences` WHERE `key` = $1")
+                .bind(key)
+                .fetch_optional(conn)
+                .await
+                .context(SqlxSnafu)
+                .context(SqlSnafu)?;
+
+        value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()
+            .context(InvalidJsonSnafu { key })
+    }
+
+    /// Set the raw JSON value of a preference, creating it if needed
+    pub async fn set_raw(
+        conn: &mut sqlx::SqliteConnection,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), PreferenceError> {
+        sqlx::query("INSERT OR REPLACE INTO `preferences` (`key`, `value`) VALUES ($1, $2)")
+            .bind(key)
+            .bind(value.to_string())
+            .execute(conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        Ok(())
+    }
+
+    /// Get whether the extension list is an exclusion list
+    pub async fn get_is_exclude_list(
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<bool, PreferenceError> {
+        let key = PreferenceKey::IsExcludeList.as_str();
+        let Some(value) = Self::get_raw(conn, key).await? else {
+            return Ok(Self::DEFAULT_IS_EXCLUDE_LIST);
+        };
+
+        value.as_bool().context(InvalidValueSnafu {
+            key,
+            value: value.clone(),
+        })
+    }
+
+    pub async fn set_is_exclude_list(
+        conn: &mut sqlx::SqliteConnection,
+        is_exclude_list: bool,
+    ) -> Result<(), PreferenceError> {
+        Self::set_raw(
+            conn,
+            PreferenceKey::IsExcludeList.as_str(),
+            &serde_json::Value::Bool(is_exclude_list),
+        )
+        .await
+    }
+
+    /// Get the list of extensions, as saved by the app
+    pub async fn get_extension_list(
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<Vec<String>, PreferenceError> {
+        let key = PreferenceKey::ExtensionList.as_str();
+        let Some(value) = Self::get_raw(conn, key).await? else {
+            return Ok(Self::DEFAULT_EXTENSION_LIST
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect());
+        };
+
+        value
+            .as_array()
+            .and_then(|list| {
+                list.iter()
+                    .map(|ext| ext.as_str().map(ToString::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .context(InvalidValueSnafu {
+                key,
+                value: value.clone(),
+            })
+    }
+
+    pub async fn set_extension_list(
+        conn: &mut sqlx::SqliteConnection,
+        extensions: &[String],
+    ) -> Result<(), PreferenceError> {
+        Self::set_raw(
+            conn,
+            PreferenceKey::ExtensionList.as_str(),
+            &serde_json::Value::from(extensions.to_vec()),
+        )
+        .await
+    }
+
+    /// Get the number of entries per page
+    pub async fn get_page_size(conn: &mut sqlx::SqliteConnection) -> Result<i64, PreferenceError> {
+        let key = PreferenceKey::PageSize.as_str();
+        let Some(value) = Self::get_raw(conn, key).await? else {
+            return Ok(Self::DEFAULT_PAGE_SIZE);
+        };
+
+        value.as_i64().context(InvalidValueSnafu {
+            key,
+            value: value.clone(),
+        })
+    }
+
+    pub async fn set_page_size(
+        conn: &mut sqlx::SqliteConnection,
+        page_size: i64,
+    ) -> Result<(), PreferenceError> {
+        Self::set_raw(
+            conn,
+            PreferenceKey::PageSize.as_str(),
+            &serde_json::Value::from(page_size),
+        )
+        .await
+    }
+
+    /// Get the extension filter configured in the app
+    pub async fn get_extension_filter(
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<ExtensionFilter, PreferenceError> {
+        Ok(ExtensionFilter::new(
+            Self::get_extension_list(conn).await?,
+            Self::get_is_exclude_list(conn).await?,
+        ))
+    }
+}
+
+/// Error for [Preferences]
+#[derive(Debug, Snafu)]
+pub enum PreferenceError {
+    #[snafu(display("The preference `{key}` isn't valid JSON"))]
+    InvalidJson {
+        key: String,
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The preference `{key}` has an unexpected value: {value}"))]
+    InvalidValue {
+        key: String,
+        value: serde_json::Value,
+        backtrace: Backtrace,
+    },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Library;
+    use crate::models::preferences::Preferences;
+
+    #[tokio::test]
+    pub async fn preferences_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = Library::create(dir.path().to_path_buf()).await.unwrap();
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        // Values written by the app
+        assert!(Preferences::get_is_exclude_list(conn).await.unwrap());
+        assert_eq!(Preferences::get_page_size(conn).await.unwrap(), 500);
+        assert_eq!(
+            Preferences::get_extension_list(conn).await.unwrap(),
+            vec![".json", ".xmp", ".aae"]
+        );
+
+        Preferences::set_is_exclude_list(conn, false).await.unwrap();
+        Preferences::set_page_size(conn, 100).await.unwrap();
+        Preferences::set_extension_list(conn, &["png".to_string(), ".JPG".to_string()])
+            .await
+            .unwrap();
+
+        let filter = Preferences::get_extension_filter(conn).await.unwrap();
+        assert!(!filter.is_exclude_list());
+        assert!(filter.is_allowed_extension("jpg"));
+        assert!(!filter.is_allowed_extension("json"));
+        assert_eq!(Preferences::get_page_size(conn).await.unwrap(), 100);
+
+        // Unknown keys
+        assert_eq!(Preferences::get_raw(conn, "MY_KEY").await.unwrap(), None);
+        Preferences::set_raw(conn, "MY_KEY", &serde_json::json!({"a": 1}))
+            .await
+            .unwrap();
+        assert_eq!(
+            Preferences::get_raw(conn, "MY_KEY").await.unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+    }
+}
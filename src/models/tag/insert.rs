@@ -1,21 +1,51 @@
 use snafu::ResultExt;
+use sqlx::AssertSqlSafe;
 use tracing::debug;
 
 use crate::Tag;
 use crate::models::errors::sqlx_error::SqlxError;
 use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
+use crate::models::library::capabilities::placeholders;
 
 impl Tag {
     /// Insert a new tag in the database
+    ///
+    /// This uses the schema capabilities cached on the connection. See [SchemaCapabilities::get_cached]
     pub async fn insert_tag(&self, conn: &mut sqlx::SqliteConnection) -> Result<Self, SqlxError> {
+        let schema = SchemaCapabilities::get_cached(conn).await?;
+        self.insert_tag_with_schema(conn, &schema).await
+    }
+
+    /// Insert a new tag in the database, only writing the columns the schema has
+    pub async fn insert_tag_with_schema(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        schema: &SchemaCapabilities,
+    ) -> Result<Self, SqlxError> {
         debug!("Adding tag `{}`", self.name);
 
-        let sql;
-        sea_query::sqlx::sqlite::query_as!(
-            sql = "INSERT INTO `tags` VALUES (NULL, {self.name}, {self.shorthand}, {self.color_namespace}, {self.color_slug}, {self.is_hidden}, {self.is_category}, {self.icon}, {self.disambiguation_id}) RETURNING *;"
-        ).fetch_one(conn)
-        .await
-        .context(SqlxSnafu)
+        let columns = schema.tag_write_columns();
+        let sql = format!(
+            "INSERT INTO `tags` ({}) VALUES ({}) RETURNING *;",
+            columns.join(", "),
+            placeholders(columns.len())
+        );
+
+        let mut query = sqlx::query_as(AssertSqlSafe(sql))
+            .bind(&self.name)
+            .bind(&self.shorthand)
+            .bind(&self.color_namespace)
+            .bind(&self.color_slug)
+            .bind(self.is_category)
+            .bind(&self.icon)
+            .bind(self.disambiguation_id);
+
+        if schema.tag_has_is_hidden {
+            query = query.bind(self.is_hidden);
+        }
+
+        query.fetch_one(conn).await.context(SqlxSnafu)
     }
 
     /// Search a tag by its name or aliases, and if not found, insert it
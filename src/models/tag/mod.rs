@@ -33,6 +33,8 @@ pub struct Tag {
     pub color_namespace: Option<String>,
     pub color_slug: Option<String>,
     pub is_category: bool,
+    /// Older libraries don't have this column, and read it as `false`
+    #[sqlx(default)]
     pub is_hidden: bool,
     pub icon: Option<String>,
     pub disambiguation_id: Option<i64>,
@@ -1,30 +1,57 @@
 use snafu::ResultExt;
+use sqlx::AssertSqlSafe;
 
 use crate::SqlxError;
 use crate::Tag;
 use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
 
 impl Tag {
+    /// Save the tag in the database
+    ///
+    /// This uses the schema capabilities cached on the connection. See [SchemaCapabilities::get_cached]
     #[cfg_attr(feature = "hotpath", hotpath::future_fn(log = true))]
     pub async fn update(&self, conn: &mut sqlx::SqliteConnection) -> Result<(), SqlxError> {
-        let sql;
-        sea_query::sqlx::sqlite::query!(
-            sql = "
-            UPDATE `tags` SET 
-                `name` = {self.name},
-                `shorthand` = {self.shorthand},
-                `color_namespace` = {self.color_namespace},
-                `color_slug` = {self.color_slug},
-                `is_hidden` = {self.is_hidden},
-                `is_category` = {self.is_category},
-                `icon` = {self.icon},
-                `disambiguation_id` = {self.disambiguation_id}
-            WHERE `id` = {self.id}
-        "
-        )
-        .execute(&mut *conn)
-        .await
-        .context(SqlxSnafu)?;
+        let schema = SchemaCapabilities::get_cached(conn).await?;
+        self.update_with_schema(conn, &schema).await
+    }
+
+    /// Save the tag in the database, only writing the columns the schema has
+    pub async fn update_with_schema(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        schema: &SchemaCapabilities,
+    ) -> Result<(), SqlxError> {
+        let columns = schema.tag_write_columns();
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| format!("`{col}` = ${}", i + 1))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "UPDATE `tags` SET {assignments} WHERE `id` = ${}",
+            columns.len() + 1
+        );
+
+        let mut query = sqlx::query(AssertSqlSafe(sql))
+            .bind(&self.name)
+            .bind(&self.shorthand)
+            .bind(&self.color_namespace)
+            .bind(&self.color_slug)
+            .bind(self.is_category)
+            .bind(&self.icon)
+            .bind(self.disambiguation_id);
+
+        if schema.tag_has_is_hidden {
+            query = query.bind(self.is_hidden);
+        }
+
+        query
+            .bind(self.id)
+            .execute(&mut *conn)
+            .await
+            .context(SqlxSnafu)?;
 
         Ok(())
     }
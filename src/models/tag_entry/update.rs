@@ -32,6 +32,7 @@ impl TagEntry {
         .await
         .context(SqlxSnafu)?;
 
+        trans.commit().await.context(SqlxSnafu)?;
         Ok(())
     }
 }
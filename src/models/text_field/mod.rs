@@ -14,10 +14,14 @@ pub mod update;
 pub struct TextField {
     #[sequelles(auto_increment)]
     pub id: i64,
-    pub name: String,
+
+    /// The key of the field's value type (Ex: `DESCRIPTION`)
+    pub type_key: String,
     pub entry_id: i64,
     pub value: Option<String>,
-    pub is_multiline: bool,
+
+    /// The display order of the field among the fields of the entry
+    pub position: i64,
 }
 
 impl TextField {
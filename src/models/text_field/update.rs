@@ -32,6 +32,7 @@ impl TextField {
         .await
         .context(SqlxSnafu)?;
 
+        trans.commit().await.context(SqlxSnafu)?;
         Ok(())
     }
 }
@@ -0,0 +1,316 @@
+use std::backtrace::Backtrace;
+
+use snafu::OptionExt as _;
+use snafu::ResultExt;
+use snafu::Snafu;
+use sqlx::FromRow;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// The keys of the field types created by the app
+pub const BUILTIN_VALUE_TYPES: [&str; 26] = [
+    "TITLE",
+    "AUTHOR",
+    "ARTIST",
+    "URL",
+    "DESCRIPTION",
+    "NOTES",
+    "COLLATION",
+    "DATE",
+    "DATE_CREATED",
+    "DATE_MODIFIED",
+    "DATE_TAKEN",
+    "DATE_PUBLISHED",
+    "BOOK",
+    "COMIC",
+    "SERIES",
+    "MANGA",
+    "SOURCE",
+    "DATE_UPLOADED",
+    "DATE_RELEASED",
+    "VOLUME",
+    "ANTHOLOGY",
+    "MAGAZINE",
+    "PUBLISHER",
+    "GUEST_ARTIST",
+    "COMPOSER",
+    "COMMENTS",
+];
+
+/// The kind of value a field type holds. This is the `type` column of `value_type`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FieldKind {
+    TextLine,
+    TextBox,
+    Tags,
+    Datetime,
+    Boolean,
+}
+
+impl FieldKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TextLine => "TEXT_LINE",
+            Self::TextBox => "TEXT_BOX",
+            Self::Tags => "TAGS",
+            Self::Datetime => "DATETIME",
+            Self::Boolean => "BOOLEAN",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "TEXT_LINE" => Some(Self::TextLine),
+            "TEXT_BOX" => Some(Self::TextBox),
+            "TAGS" => Some(Self::Tags),
+            "DATETIME" => Some(Self::Datetime),
+            "BOOLEAN" => Some(Self::Boolean),
+            _ => None,
+        }
+    }
+
+    /// The table storing the fields of this kind, if any
+    pub fn field_table(&self) -> Option<&'static str> {
+        match self {
+            Self::TextLine | Self::TextBox => Some("text_fields"),
+            Self::Datetime => Some("datetime_fields"),
+            Self::Boolean => Some("boolean_fields"),
+            Self::Tags => None,
+        }
+    }
+}
+
+/// A field type, like `TITLE` or `DATE_TAKEN`
+#[derive(Debug, FromRow, Clone, PartialEq, Eq)]
+pub struct ValueType {
+    pub key: String,
+    pub name: String,
+
+    /// The raw kind of the field. See [ValueType::kind]
+    #[sqlx(rename = "type")]
+    pub field_type: String,
+    pub is_default: bool,
+    pub position: i64,
+}
+
+impl ValueType {
+    /// The kind of value of the field. `None` if the app wrote a kind unknown to the crate
+    pub fn kind(&self) -> Option<FieldKind> {
+        FieldKind::parse(&self.field_type)
+    }
+
+    /// Return true if the type is one of the app
+    pub fn is_builtin(&self) -> bool {
+        BUILTIN_VALUE_TYPES.contains(&self.key.as_str())
+    }
+
+    /// Get all the field types, built-in and custom, in display order
+    pub async fn find_all(conn: &mut sqlx::SqliteConnection) -> Result<Vec<Self>, SqlxError> {
+        sqlx::query_as("SELECT * FROM `value_type` ORDER BY `position`, `key`")
+            .fetch_all(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Get the custom field types, in display order
+    pub async fn find_custom(conn: &mut sqlx::SqliteConnection) -> Result<Vec<Self>, SqlxError> {
+        Ok(Self::find_all(conn)
+            .await?
+            .into_iter()
+            .filter(|value_type| !value_type.is_builtin())
+            .collect())
+    }
+
+    pub async fn find_by_key(
+        conn: &mut sqlx::SqliteConnection,
+        key: &str,
+    ) -> Result<Option<Self>, SqlxError> {
+        sqlx::query_as("SELECT * FROM `value_type` WHERE `key` = $1")
+            .bind(key)
+            .fetch_optional(conn)
+            .await
+            .context(SqlxSnafu)
+    }
+
+    /// Create a custom field type. The key is made from the name (Ex: `Catalog Number` gets `CATALOG_NUMBER`)
+    pub async fn create_custom(
+        conn: &mut sqlx::SqliteConnection,
+        name: &str,
+        kind: FieldKind,
+    ) -> Result<Self, ValueTypeError> {
+        let key = Self::key_from_name(name);
+        if key.is_empty() {
+            return InvalidNameSnafu { name }.fail();
+        }
+
+        if Self::find_by_key(conn, &key)
+            .await
+            .context(SqlSnafu)?
+            .is_some()
+        {
+            return AlreadyExistsSnafu { key }.fail();
+        }
+
+        sqlx::query_as(
+            "INSERT INTO `value_type` (`key`, `name`, `type`, `is_default`, `position`)
+            VALUES ($1, $2, $3, 0, (SELECT COALESCE(MAX(`position`) + 1, 0) FROM `value_type`))
+            RETURNING *",
+        )
+        .bind(key)
+        .bind(name)
+        .bind(kind.as_str())
+        .fetch_one(conn)
+        .await
+        .context(SqlxSnafu)
+        .context(SqlSnafu)
+    }
+
+    /// Rename a custom field type. The key stays the same, so the existing fields keep their type
+    pub async fn rename(
+        &mut self,
+        conn: &mut sqlx::SqliteConnection,
+        name: &str,
+    ) -> Result<(), ValueTypeError> {
+        if self.is_builtin() {
+            return BuiltinSnafu {
+                key: self.key.clone(),
+            }
+            .fail();
+        }
+
+        sqlx::query("UPDATE `value_type` SET `name` = $1 WHERE `key` = $2")
+            .bind(name)
+            .bind(&self.key)
+            .execute(conn)
+            .await
+            .context(SqlxSnafu)
+            .context(SqlSnafu)?;
+
+        self.name = name.to_string();
+        Ok(())
+    }
+
+    /// Check that the field type exists, and that its fields are stored in `table`
+    pub async fn check_field_table(
+        conn: &mut sqlx::SqliteConnection,
+        key: &str,
+        table: &str,
+    ) -> Result<Self, ValueTypeError> {
+        let value_type = Self::find_by_key(conn, key)
+            .await
+            .context(SqlSnafu)?
+            .context(UnknownKeySnafu { key })?;
+
+        let kind_table = value_type.kind().and_then(|kind| kind.field_table());
+        if kind_table != Some(table) {
+            return WrongKindSnafu {
+                key,
+                field_type: value_type.field_type,
+            }
+            .fail();
+        }
+
+        Ok(value_type)
+    }
+
+    fn key_from_name(name: &str) -> String {
+        name.trim()
+            .split(|char: char| !char.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_uppercase())
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+}
+
+/// Error for [ValueType]
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum ValueTypeError {
+    #[snafu(display("There is no field type with the key `{key}`"))]
+    UnknownKey { key: String, backtrace: Backtrace },
+
+    #[snafu(display("The field type `{key}` holds `{field_type}` values"))]
+    WrongKind {
+        key: String,
+        field_type: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("The field type `{key}` already exists"))]
+    AlreadyExists { key: String, backtrace: Backtrace },
+
+    #[snafu(display("`{name}` can't be used as a field type name"))]
+    InvalidName { name: String, backtrace: Backtrace },
+
+    #[snafu(display("The field type `{key}` is built in the app, and can't be modified"))]
+    Builtin { key: String, backtrace: Backtrace },
+
+    Sql {
+        #[snafu(backtrace)]
+        source: SqlxError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::value_type::FieldKind;
+    use crate::models::value_type::ValueType;
+    use crate::models::value_type::ValueTypeError;
+    use crate::tests::fixtures::raw_library::get_empty_library;
+
+    #[tokio::test]
+    pub async fn custom_value_type_test() {
+        let lib = get_empty_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        let builtins = ValueType::find_all(conn).await.unwrap();
+        assert!(builtins.iter().all(|value_type| value_type.is_builtin()));
+
+        let mut catalog = ValueType::create_custom(conn, "Catalog Number", FieldKind::TextLine)
+            .await
+            .unwrap();
+        assert_eq!(catalog.key, "CATALOG_NUMBER");
+        assert_eq!(
+            ValueType::find_custom(conn).await.unwrap(),
+            vec![catalog.clone()]
+        );
+        assert!(matches!(
+            ValueType::create_custom(conn, "catalog number", FieldKind::TextBox).await,
+            Err(ValueTypeError::AlreadyExists { .. })
+        ));
+
+        catalog.rename(conn, "Catalog N°").await.unwrap();
+        assert_eq!(
+            ValueType::find_by_key(conn, "CATALOG_NUMBER")
+                .await
+                .unwrap()
+                .unwrap()
+                .name,
+            "Catalog N°"
+        );
+
+        let mut title = ValueType::find_by_key(conn, "TITLE")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            title.rename(conn, "Name").await,
+            Err(ValueTypeError::Builtin { .. })
+        ));
+
+        // Check the kinds
+        ValueType::check_field_table(conn, "CATALOG_NUMBER", "text_fields")
+            .await
+            .unwrap();
+        assert!(matches!(
+            ValueType::check_field_table(conn, "DATE", "text_fields").await,
+            Err(ValueTypeError::WrongKind { .. })
+        ));
+        assert!(matches!(
+            ValueType::check_field_table(conn, "CATALOG", "text_fields").await,
+            Err(ValueTypeError::UnknownKey { .. })
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use core::ops::AddAssign as _;
+use core::ops::Bound;
+
+use chrono::NaiveDateTime;
+
+use crate::models::datetime_field::datetime_value_sql;
+use crate::models::datetime_field::format_datetime_value;
+use crate::query::SQLQuery;
+use crate::query::entry_search_query::EntrySearchQuery;
+use crate::query::trait_entry_filter::QueryEntryFilter;
+
+/// Entries with a datetime field of the type whose value is within the bounds. An unbounded side isn't checked.
+///
+/// Values that can't be parsed never match. See [DatetimeField::find_invalid](crate::models::datetime_field::DatetimeField::find_invalid)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DatetimeFieldRange {
+    pub field_type: String,
+    pub start: Bound<NaiveDateTime>,
+    pub end: Bound<NaiveDateTime>,
+}
+
+impl DatetimeFieldRange {
+    /// Fields strictly before `end`
+    pub fn before(field_type: impl Into<String>, end: NaiveDateTime) -> Self {
+        Self {
+            field_type: field_type.into(),
+            start: Bound::Unbounded,
+            end: Bound::Excluded(end),
+        }
+    }
+
+    /// Fields at or before `end`
+    pub fn at_or_before(field_type: impl Into<String>, end: NaiveDateTime) -> Self {
+        Self {
+            field_type: field_type.into(),
+            start: Bound::Unbounded,
+            end: Bound::Included(end),
+        }
+    }
+
+    /// Fields strictly after `start`
+    pub fn after(field_type: impl Into<String>, start: NaiveDateTime) -> Self {
+        Self {
+            field_type: field_type.into(),
+            start: Bound::Excluded(start),
+            end: Bound::Unbounded,
+        }
+    }
+
+    /// Fields at or after `start`
+    pub fn at_or_after(field_type: impl Into<String>, start: NaiveDateTime) -> Self {
+        Self {
+            field_type: field_type.into(),
+            start: Bound::Included(start),
+            end: Bound::Unbounded,
+        }
+    }
+
+    /// Fields at or after `start`, and strictly before `end`
+    pub fn between(
+        field_type: impl Into<String>,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Self {
+        Self {
+            field_type: field_type.into(),
+            start: Bound::Included(start),
+            end: Bound::Excluded(end),
+        }
+    }
+
+    /// The bounds with their comparison operator, in the order they get bound
+    fn bounds(&self) -> impl Iterator<Item = (&'static str, &NaiveDateTime)> {
+        let start = match &self.start {
+            Bound::Included(start) => Some((">=", start)),
+            Bound::Excluded(start) => Some((">", start)),
+            Bound::Unbounded => None,
+        };
+        let end = match &self.end {
+            Bound::Included(end) => Some(("<=", end)),
+            Bound::Excluded(end) => Some(("<", end)),
+            Bound::Unbounded => None,
+        };
+
+        start.into_iter().chain(end)
+    }
+}
+
+impl QueryEntryFilter for DatetimeFieldRange {
+    fn get_where_condition(&self, bind_id: &mut u64) -> Option<String> {
+        let type_id = *bind_id;
+        bind_id.add_assign(1);
+
+        let value = datetime_value_sql("`value`");
+        let mut conditions = vec![
+            format!("`type_key` = ${type_id}"),
+            format!("{value} IS NOT NULL"),
+        ];
+
+        for (operator, _) in self.bounds() {
+            let bound_id = *bind_id;
+            bind_id.add_assign(1);
+            conditions.push(format!("{value} {operator} ${bound_id}"));
+        }
+
+        Some(format!(
+            "`entries`.`id` IN (
+                    SELECT `entry_id`
+                    FROM `datetime_fields`
+                    WHERE {}
+                )",
+            conditions.join(" AND ")
+        ))
+    }
+
+    fn bind<'q, O>(&'q self, query: SQLQuery<'q, O>) -> SQLQuery<'q, O> {
+        let mut query = query.bind(&self.field_type);
+
+        for (_, bound) in self.bounds() {
+            query = query.bind(format_datetime_value(bound));
+        }
+
+        query
+    }
+}
+
+impl From<DatetimeFieldRange> for EntrySearchQuery {
+    fn from(value: DatetimeFieldRange) -> Self {
+        EntrySearchQuery::DatetimeFieldRange(value)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use chrono::NaiveDate;
+    use chrono::NaiveDateTime;
+    use itertools::Itertools as _;
+
+    use crate::Entry;
+    use crate::query::datetime_field_range::DatetimeFieldRange;
+    use crate::query::entry_search_query::EntrySearchQuery;
+    use crate::query::query_options::EntryQueryOptions;
+    use crate::query::query_options::EntrySortField;
+    use crate::query::query_options::SortDirection;
+    use crate::query::trait_entry_filter::QueryEntryFilter as _;
+    use crate::tests::fixtures::data::get_test_library;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    pub async fn datetime_field_range_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+
+        for (id, value, position) in [
+            (1, "2019-06-01 12:00:00", 0),
+            // Legacy formats
+            (2, "2020-01-01T08:30:00.250", 0),
+            (3, "2021-02-03", 0),
+            (4, "Last summer", 0),
+            // Accepted by SQLite's `datetime`, but not valid datetimes
+            (4, "now", 1),
+            (5, "2024", 0),
+        ] {
+            sqlx::query(
+                "INSERT INTO `datetime_fields` (`type_key`, `entry_id`, `value`, `position`) VALUES ('DATE_TAKEN', $1, $2, $3)",
+            )
+            .bind(id)
+            .bind(value)
+            .bind(position)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        }
+
+        let filenames = |entries: Vec<Entry>| {
+            entries
+                .into_iter()
+                .map(|entry| entry.filename)
+                .collect_vec()
+        };
+
+        let since_2020 = DatetimeFieldRange::after("DATE_TAKEN", date(2020, 1, 1));
+        let options = EntryQueryOptions::default().sort_by(
+            EntrySortField::DatetimeField("DATE_TAKEN".to_string()),
+            SortDirection::Descending,
+        );
+        assert_eq!(
+            filenames(since_2020.fetch_page(conn, &options).await.unwrap()),
+            vec!["doge_and_maxwell.png", "doge.png"]
+        );
+
+        // Only the inclusive bounds match a value equal to them
+        let day = date(2021, 2, 3);
+        for (range, expected) in [
+            (DatetimeFieldRange::after("DATE_TAKEN", day), vec![]),
+            (
+                DatetimeFieldRange::at_or_after("DATE_TAKEN", day),
+                vec!["doge_and_maxwell.png"],
+            ),
+            (
+                DatetimeFieldRange::before("DATE_TAKEN", day),
+                vec!["maxwell.png", "doge.png"],
+            ),
+            (
+                DatetimeFieldRange::at_or_before("DATE_TAKEN", day),
+                vec!["maxwell.png", "doge.png", "doge_and_maxwell.png"],
+            ),
+        ] {
+            assert_eq!(
+                filenames(range.fetch_all(conn).await.unwrap()),
+                expected,
+                "{range:?}"
+            );
+        }
+
+        let in_2019 = DatetimeFieldRange::between("DATE_TAKEN", date(2019, 1, 1), date(2020, 1, 1));
+        assert_eq!(
+            filenames(in_2019.fetch_all(conn).await.unwrap()),
+            vec!["maxwell.png"]
+        );
+
+        // Entries without a valid date come last in a descending order
+        let sorted = EntrySearchQuery::from(in_2019)
+            .invert()
+            .fetch_page(conn, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            filenames(sorted),
+            vec!["doge_and_maxwell.png", "doge.png", "away.png", "OIIA.png"]
+        );
+    }
+}
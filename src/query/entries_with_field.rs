@@ -0,0 +1,62 @@
+use core::ops::AddAssign as _;
+
+use crate::query::SQLQuery;
+use crate::query::entry_search_query::EntrySearchQuery;
+use crate::query::trait_entry_filter::QueryEntryFilter;
+
+/// Entries having at least one field of the type, even if empty. Invert it to get the entries missing the field
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EntriesWithField(pub String);
+
+impl QueryEntryFilter for EntriesWithField {
+    fn get_where_condition(&self, bind_id: &mut u64) -> Option<String> {
+        let id = *bind_id;
+        bind_id.add_assign(1);
+
+        Some(format!(
+            "`entries`.`id` IN (
+                    SELECT `entry_id` FROM `boolean_fields` WHERE `type_key` = ${id}
+                    UNION
+                    SELECT `entry_id` FROM `datetime_fields` WHERE `type_key` = ${id}
+                    UNION
+                    SELECT `entry_id` FROM `text_fields` WHERE `type_key` = ${id}
+                )"
+        ))
+    }
+
+    fn bind<'q, O>(&'q self, query: SQLQuery<'q, O>) -> SQLQuery<'q, O> {
+        query.bind(&self.0)
+    }
+}
+
+impl From<EntriesWithField> for EntrySearchQuery {
+    fn from(value: EntriesWithField) -> Self {
+        EntrySearchQuery::EntriesWithField(value)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::query::entries_with_field::EntriesWithField;
+    use crate::query::entry_search_query::EntrySearchQuery;
+    use crate::tests::fixtures::assertions::assert_eq_entries_in;
+    use crate::tests::fixtures::data::get_test_library_with_fields;
+
+    #[tokio::test]
+    pub async fn entries_with_field_test() {
+        let lib = get_test_library_with_fields().await;
+
+        assert_eq_entries_in(
+            &lib,
+            EntriesWithField("TITLE".to_string()),
+            vec!["doge_and_maxwell.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            EntrySearchQuery::from(EntriesWithField("DATE_TAKEN".to_string())).invert(),
+            vec!["doge_and_maxwell.png", "OIIA.png", "somwhere/far/away.png"],
+        )
+        .await;
+    }
+}
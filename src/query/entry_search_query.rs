@@ -5,6 +5,8 @@ use nom_language::error::convert_error;
 use snafu::Snafu;
 
 use crate::query::and::QueryAnd;
+use crate::query::datetime_field_range::DatetimeFieldRange;
+use crate::query::entries_with_field::EntriesWithField;
 use crate::query::entries_with_tags::EntriesWithTags;
 use crate::query::eq_absolute_path::EqAbsolutePath;
 use crate::query::eq_any_entry_id::EqAnyEntryId;
@@ -16,6 +18,7 @@ use crate::query::not::QueryNot;
 use crate::query::or::QueryOr;
 use crate::query::parse_expression;
 use crate::query::tag_search_query::TagSearchQuery;
+use crate::query::text_field_match::TextFieldMatch;
 use crate::query::trait_entry_filter::QueryEntryFilter;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -26,6 +29,9 @@ pub enum EntrySearchQuery {
     EqEntryFolder(EqEntryFolder),
     EqEntryField(EqEntryField),
     EqAbsolutePath(EqAbsolutePath),
+    DatetimeFieldRange(DatetimeFieldRange),
+    TextFieldMatch(TextFieldMatch),
+    EntriesWithField(EntriesWithField),
 
     EntriesWithTags(EntriesWithTags<Box<TagSearchQuery>>),
     Not(QueryNot<Box<EntrySearchQuery>>),
@@ -43,6 +49,9 @@ impl QueryEntryFilter for EntrySearchQuery {
             Self::EqEntryFolder(val) => val.get_where_condition(bind_id),
             Self::EqEntryField(val) => val.get_where_condition(bind_id),
             Self::EqAbsolutePath(val) => val.get_where_condition(bind_id),
+            Self::DatetimeFieldRange(val) => val.get_where_condition(bind_id),
+            Self::TextFieldMatch(val) => val.get_where_condition(bind_id),
+            Self::EntriesWithField(val) => val.get_where_condition(bind_id),
             Self::EntriesWithTags(val) => val.get_where_condition(bind_id),
             Self::Not(val) => val.get_where_condition(bind_id),
             Self::And(val) => val.get_where_condition(bind_id),
@@ -58,6 +67,9 @@ impl QueryEntryFilter for EntrySearchQuery {
             Self::EqEntryFolder(val) => val.bind(query),
             Self::EqEntryField(val) => val.bind(query),
             Self::EqAbsolutePath(val) => val.bind(query),
+            Self::DatetimeFieldRange(val) => val.bind(query),
+            Self::TextFieldMatch(val) => val.bind(query),
+            Self::EntriesWithField(val) => val.bind(query),
             Self::EntriesWithTags(val) => val.bind(query),
             Self::Not(val) => val.bind(query),
             Self::And(val) => val.bind(query),
@@ -1,5 +1,6 @@
 use core::ops::AddAssign as _;
 
+use crate::models::datetime_field::datetime_value_sql;
 use crate::query::SQLQuery;
 use crate::query::entry_search_query::EntrySearchQuery;
 use crate::query::trait_entry_filter::QueryEntryFilter;
@@ -13,6 +14,8 @@ pub struct EqEntryField {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum FieldValue {
     Boolean(bool),
+
+    /// Match the datetime fields on this day, whatever their time and the format they were written in
     Datetime(chrono::NaiveDate),
     Text(String),
 }
@@ -24,23 +27,21 @@ impl QueryEntryFilter for EqEntryField {
         let value_id = *bind_id;
         bind_id.add_assign(1);
 
+        // Each kind of value is only compared against the fields storing it
+        let (table, value) = match self.value {
+            FieldValue::Boolean(_) => ("boolean_fields", "`value`".to_string()),
+            FieldValue::Datetime(_) => (
+                "datetime_fields",
+                format!("date({})", datetime_value_sql("`value`")),
+            ),
+            FieldValue::Text(_) => ("text_fields", "`value`".to_string()),
+        };
+
         Some(format!(
             "`entries`.`id` IN (
                     SELECT `entry_id` 
-                    FROM `boolean_fields`
-                    WHERE `type_key` = ${type_id} AND `value` = ${value_id}
-
-                    UNION
-
-                    SELECT `entry_id` 
-                    FROM `datetime_fields`
-                    WHERE `type_key` = ${type_id} AND `value` = ${value_id}
-
-                    UNION
-
-                    SELECT `entry_id` 
-                    FROM `text_fields`
-                    WHERE `type_key` = ${type_id} AND `value` = ${value_id}
+                    FROM `{table}`
+                    WHERE `type_key` = ${type_id} AND {value} = ${value_id}
                 )"
         ))
     }
@@ -50,7 +51,7 @@ impl QueryEntryFilter for EqEntryField {
 
         match &self.value {
             FieldValue::Boolean(val) => query.bind(val),
-            FieldValue::Datetime(val) => query.bind(val),
+            FieldValue::Datetime(val) => query.bind(val.format("%Y-%m-%d").to_string()),
             FieldValue::Text(val) => query.bind(val),
         }
     }
@@ -62,21 +63,47 @@ impl From<EqEntryField> for EntrySearchQuery {
     }
 }
 
-// #[cfg(test)]
-// pub mod test {
-//     use crate::query::eq_entry_field::EqEntryField;
-//     use crate::query::eq_entry_field::FieldValue;
-//     use crate::tests::fixtures::assertions::assert_eq_entries;
-
-//     #[tokio::test]
-//     pub async fn eq_entry_id_test() {
-//         assert_eq_entries(
-//             EqEntryField {
-//                 field_type: "DESCRIPTION".into(),
-//                 value: FieldValue::Text("A very dingus cat".to_string()),
-//             },
-//             vec![0],
-//         )
-//         .await;
-//     }
-// }
+#[cfg(test)]
+pub mod test {
+    use chrono::NaiveDate;
+
+    use crate::query::eq_entry_field::EqEntryField;
+    use crate::query::eq_entry_field::FieldValue;
+    use crate::tests::fixtures::assertions::assert_eq_entries_in;
+    use crate::tests::fixtures::data::get_test_library_with_fields;
+
+    #[tokio::test]
+    pub async fn eq_entry_field_test() {
+        let lib = get_test_library_with_fields().await;
+
+        assert_eq_entries_in(
+            &lib,
+            EqEntryField {
+                field_type: "DESCRIPTION".into(),
+                value: FieldValue::Text("A very dingus cat".to_string()),
+            },
+            vec!["maxwell.png"],
+        )
+        .await;
+
+        assert_eq_entries_in(
+            &lib,
+            EqEntryField {
+                field_type: "DATE_TAKEN".into(),
+                value: FieldValue::Datetime(NaiveDate::from_ymd_opt(2021, 2, 3).unwrap()),
+            },
+            vec!["doge.png"],
+        )
+        .await;
+
+        assert_eq_entries_in(
+            &lib,
+            EqEntryField {
+                field_type: "FAVORITE".into(),
+                value: FieldValue::Boolean(false),
+            },
+            vec!["doge.png"],
+        )
+        .await;
+    }
+}
@@ -1,4 +1,6 @@
 pub mod and;
+pub mod datetime_field_range;
+pub mod entries_with_field;
 pub mod entries_with_tags;
 pub mod entry_search_query;
 pub mod eq_absolute_path;
@@ -14,13 +16,156 @@ pub mod eq_tag_string;
 pub mod not;
 pub mod or;
 pub mod parsing;
+pub mod query_options;
 pub mod tag_search_query;
+pub mod text_field_match;
 pub mod trait_entry_filter;
 pub mod trait_tag_filter;
 
-use crate::query::parsing::expression::parse_expression;
+use futures::StreamExt as _;
+use futures::stream::BoxStream;
+use snafu::ResultExt as _;
+use sqlx::AssertSqlSafe;
+use sqlx::FromRow;
 use sqlx::Sqlite;
 use sqlx::query::QueryAs;
 use sqlx::sqlite::SqliteArguments;
+use sqlx::sqlite::SqliteRow;
+
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
+use crate::query::parsing::expression::parse_expression;
 
 pub type SQLQuery<'q, O> = QueryAs<'q, Sqlite, O, SqliteArguments>;
+
+/// Turn an optional condition into a `WHERE` clause
+pub(crate) fn where_clause(condition: Option<String>) -> String {
+    condition
+        .map(|cond| format!(" WHERE ({cond})"))
+        .unwrap_or_default()
+}
+
+// Terminal operations shared by the entry and tag filters. `bind` is the `bind` method of the filter
+
+/// Create a `SELECT` of the whole rows of `table` matching the condition.
+///
+/// The columns are picked by `columns` from the capabilities of the connection's schema, so the columns of older versions are handled
+pub(crate) async fn select_rows_sql(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    columns: fn(&SchemaCapabilities) -> String,
+    condition: Option<String>,
+) -> Result<String, SqlxError> {
+    let schema = SchemaCapabilities::get_cached(conn).await?;
+    Ok(format!(
+        "SELECT {} FROM `{table}`{}",
+        columns(&schema),
+        where_clause(condition)
+    ))
+}
+
+/// Fetch all the rows of a `SELECT`
+pub(crate) async fn fetch_all_rows<'q, O, B>(
+    conn: &mut sqlx::SqliteConnection,
+    sql: String,
+    bind: B,
+) -> Result<Vec<O>, SqlxError>
+where
+    O: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    B: FnOnce(SQLQuery<'q, O>) -> SQLQuery<'q, O>,
+{
+    bind(sqlx::query_as(AssertSqlSafe(sql)))
+        .fetch_all(conn)
+        .await
+        .context(SqlxSnafu)
+}
+
+/// Fetch the first row of a `SELECT`
+pub(crate) async fn fetch_optional_row<'q, O, B>(
+    conn: &mut sqlx::SqliteConnection,
+    sql: String,
+    bind: B,
+) -> Result<Option<O>, SqlxError>
+where
+    O: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    B: FnOnce(SQLQuery<'q, O>) -> SQLQuery<'q, O>,
+{
+    bind(sqlx::query_as(AssertSqlSafe(format!("{sql} LIMIT 1"))))
+        .fetch_optional(conn)
+        .await
+        .context(SqlxSnafu)
+}
+
+/// Stream the rows of a `SELECT`
+pub(crate) fn stream_rows<'l, O, B>(
+    conn: &'l mut sqlx::SqliteConnection,
+    sql: String,
+    bind: B,
+) -> BoxStream<'l, Result<O, SqlxError>>
+where
+    O: for<'r> FromRow<'r, SqliteRow> + Send + Unpin + 'l,
+    B: FnOnce(SQLQuery<'l, O>) -> SQLQuery<'l, O>,
+{
+    bind(sqlx::query_as(AssertSqlSafe(sql)))
+        .fetch(conn)
+        .map(|val| val.context(SqlxSnafu))
+        .boxed()
+}
+
+/// Fetch the ids of the rows of `table` matching the condition
+pub(crate) async fn fetch_row_ids<'q, B>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    condition: Option<String>,
+    bind: B,
+) -> Result<Vec<i64>, SqlxError>
+where
+    B: FnOnce(SQLQuery<'q, (i64,)>) -> SQLQuery<'q, (i64,)>,
+{
+    let sql = format!(
+        "SELECT `{table}`.`id` FROM `{table}`{}",
+        where_clause(condition)
+    );
+    let rows = fetch_all_rows(conn, sql, bind).await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Count the rows of `table` matching the condition
+pub(crate) async fn count_rows<'q, B>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    condition: Option<String>,
+    bind: B,
+) -> Result<i64, SqlxError>
+where
+    B: FnOnce(SQLQuery<'q, (i64,)>) -> SQLQuery<'q, (i64,)>,
+{
+    let sql = format!("SELECT COUNT(*) FROM `{table}`{}", where_clause(condition));
+    let (count,) = bind(sqlx::query_as(AssertSqlSafe(sql)))
+        .fetch_one(conn)
+        .await
+        .context(SqlxSnafu)?;
+    Ok(count)
+}
+
+/// Return true if at least one row of `table` matches the condition
+pub(crate) async fn rows_exist<'q, B>(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    condition: Option<String>,
+    bind: B,
+) -> Result<bool, SqlxError>
+where
+    B: FnOnce(SQLQuery<'q, (bool,)>) -> SQLQuery<'q, (bool,)>,
+{
+    let sql = format!(
+        "SELECT EXISTS(SELECT 1 FROM `{table}`{})",
+        where_clause(condition)
+    );
+    let (exists,) = bind(sqlx::query_as(AssertSqlSafe(sql)))
+        .fetch_one(conn)
+        .await
+        .context(SqlxSnafu)?;
+    Ok(exists)
+}
@@ -12,6 +12,8 @@ use crate::query::entry_search_query::EntrySearchQuery;
 use crate::query::parsing::and::parse_explicit_and;
 use crate::query::parsing::and::parse_implicit_and;
 use crate::query::parsing::delimited_cut;
+use crate::query::parsing::field::parse_field_predicate;
+use crate::query::parsing::field::parse_field_presence;
 use crate::query::parsing::not::parse_explicit_not;
 use crate::query::parsing::or::parse_explicit_or;
 use crate::query::parsing::sp;
@@ -80,6 +82,8 @@ where
             sp,
             alt((
                 parse_tag_id.map(|elem| TagSearchQuery::from(elem).into_entry_search_query()),
+                parse_field_presence,
+                parse_field_predicate,
                 map(parse_tag_string, EntrySearchQuery::from),
                 map(parse_tag_string_escaped, EntrySearchQuery::from),
                 map(parse_explicit_not, EntrySearchQuery::from),
@@ -0,0 +1,394 @@
+use nom::IResult;
+use nom::Parser as _;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::bytes::complete::tag_no_case;
+use nom::bytes::complete::take_while;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::char;
+use nom::combinator::cut;
+use nom::combinator::map;
+use nom::combinator::map_opt;
+use nom::error::ContextError;
+use nom::error::ParseError;
+use nom::error::context;
+use nom::sequence::delimited;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+
+use crate::models::datetime_field::parse_datetime_value;
+use crate::query::datetime_field_range::DatetimeFieldRange;
+use crate::query::entries_with_field::EntriesWithField;
+use crate::query::entry_search_query::EntrySearchQuery;
+use crate::query::eq_entry_field::EqEntryField;
+use crate::query::eq_entry_field::FieldValue;
+use crate::query::parsing::sp;
+use crate::query::text_field_match::TextFieldMatch;
+use crate::query::text_field_match::TextMatchMode;
+
+/// Parse `has:field_key` and `missing:field_key`
+pub(super) fn parse_field_presence<'a, E>(input: &'a str) -> IResult<&'a str, EntrySearchQuery, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    let has = preceded(terminated(tag_no_case("has"), char(':')), parse_field_key)
+        .map(|key| EntrySearchQuery::from(EntriesWithField(key)));
+    let missing = preceded(
+        terminated(tag_no_case("missing"), char(':')),
+        parse_field_key,
+    )
+    .map(|key| EntrySearchQuery::from(EntriesWithField(key)).invert());
+
+    context("Field presence", preceded(sp, alt((has, missing)))).parse(input)
+}
+
+/// Parse a field predicate, like `author:"Jane"` or `date_taken:>2020-01-01`.
+///
+/// The value can be:
+/// - `>date`, `>=date`, `<date`, `<=date` or `date..date` to compare datetime fields. `date..date` includes the start, but not the end
+/// - `^text` for the text fields starting with `text`
+/// - `=text` for the text fields equal to `text`
+/// - Anything else is searched in the text fields, and matched against the datetime fields of the same day and the boolean fields
+///
+/// Text comparisons ignore the case of the ASCII letters. See [TextMatchMode].
+///
+/// As it runs before the tag string parser, any `word:` is taken as a field key. So `TAG_ID:5` and `tag_id:abc` search the `TAG_ID` field
+pub(super) fn parse_field_predicate<'a, E>(input: &'a str) -> IResult<&'a str, EntrySearchQuery, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    // Once the `field:` prefix is found, the value has to be valid
+    let (leftover_input, key) = context(
+        "Field key",
+        preceded(sp, terminated(parse_field_key, char(':'))),
+    )
+    .parse(input)?;
+
+    // The inclusive operators are tried first, as they start like the strict ones
+    let at_or_after = preceded(
+        tag(">="),
+        cut(map_opt(parse_field_value, parse_datetime_value)),
+    )
+    .map(|start| EntrySearchQuery::from(DatetimeFieldRange::at_or_after(&key, start)));
+    let after = preceded(
+        char('>'),
+        cut(map_opt(parse_field_value, parse_datetime_value)),
+    )
+    .map(|start| EntrySearchQuery::from(DatetimeFieldRange::after(&key, start)));
+    let at_or_before = preceded(
+        tag("<="),
+        cut(map_opt(parse_field_value, parse_datetime_value)),
+    )
+    .map(|end| EntrySearchQuery::from(DatetimeFieldRange::at_or_before(&key, end)));
+    let before = preceded(
+        char('<'),
+        cut(map_opt(parse_field_value, parse_datetime_value)),
+    )
+    .map(|end| EntrySearchQuery::from(DatetimeFieldRange::before(&key, end)));
+    let between = map_opt(parse_field_value, |value| {
+        let (start, end) = value.split_once("..")?;
+        Some(EntrySearchQuery::from(DatetimeFieldRange::between(
+            &key,
+            parse_datetime_value(start)?,
+            parse_datetime_value(end)?,
+        )))
+    });
+    let starts_with = preceded(char('^'), parse_field_value)
+        .map(|value| text_match(&key, value, TextMatchMode::StartsWith));
+    let equals = preceded(char('='), parse_field_value)
+        .map(|value| text_match(&key, value, TextMatchMode::Equals));
+    let any = map(parse_field_value, |value| any_match(&key, value));
+
+    context(
+        "Field value",
+        cut(alt((
+            at_or_after,
+            after,
+            at_or_before,
+            before,
+            between,
+            starts_with,
+            equals,
+            any,
+        ))),
+    )
+    .parse(leftover_input)
+}
+
+/// Parse a field key. Keys are uppercase, so `date_taken` gives `DATE_TAKEN`
+fn parse_field_key<'a, E>(input: &'a str) -> IResult<&'a str, String, E>
+where
+    E: ParseError<&'a str>,
+{
+    map(
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        str::to_uppercase,
+    )
+    .parse(input)
+}
+
+/// Parse a quoted or bare value
+fn parse_field_value<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str>,
+{
+    alt((
+        delimited(char('"'), take_while(|c: char| c != '"'), char('"')),
+        take_while1(|c: char| !c.is_whitespace() && !"()\"".contains(c)),
+    ))
+    .parse(input)
+}
+
+fn text_match(key: &str, value: &str, mode: TextMatchMode) -> EntrySearchQuery {
+    TextFieldMatch {
+        field_type: key.to_string(),
+        value: value.to_string(),
+        mode,
+    }
+    .into()
+}
+
+/// The parser doesn't know the kind of the field, so try all the kinds the value can be.
+/// Only the table of the field's kind holds fields of its type, so the others never match
+fn any_match(key: &str, value: &str) -> EntrySearchQuery {
+    let mut query = text_match(key, value, TextMatchMode::Contains);
+
+    if let Some(datetime) = parse_datetime_value(value) {
+        query = query.or(EqEntryField {
+            field_type: key.to_string(),
+            value: FieldValue::Datetime(datetime.date()),
+        }
+        .into());
+    }
+
+    if let Ok(boolean) = value.to_lowercase().parse::<bool>() {
+        query = query.or(EqEntryField {
+            field_type: key.to_string(),
+            value: FieldValue::Boolean(boolean),
+        }
+        .into());
+    }
+
+    query
+}
+
+#[cfg(test)]
+pub mod test {
+    use chrono::NaiveDate;
+    use nom_language::error::VerboseError;
+
+    use crate::query::datetime_field_range::DatetimeFieldRange;
+    use crate::query::entries_with_field::EntriesWithField;
+    use crate::query::entry_search_query::EntrySearchQuery;
+    use crate::query::eq_entry_field::EqEntryField;
+    use crate::query::eq_entry_field::FieldValue;
+    use crate::query::eq_tag_id::EqTagId;
+    use crate::query::parsing::assert_nom;
+    use crate::query::parsing::expression::parse_filter_token;
+    use crate::query::parsing::field::parse_field_predicate;
+    use crate::query::parsing::field::parse_field_presence;
+    use crate::query::tag_search_query::TagSearchQuery;
+    use crate::query::text_field_match::TextFieldMatch;
+    use crate::query::text_field_match::TextMatchMode;
+    use crate::tests::fixtures::assertions::assert_eq_entries_in;
+    use crate::tests::fixtures::data::get_test_library_with_fields;
+
+    fn author(value: &str, mode: TextMatchMode) -> EntrySearchQuery {
+        TextFieldMatch {
+            field_type: "AUTHOR".to_string(),
+            value: value.to_string(),
+            mode,
+        }
+        .into()
+    }
+
+    #[test]
+    pub fn parse_field_presence_test() {
+        assert_nom(
+            " has:title ",
+            parse_field_presence,
+            (" ", EntriesWithField("TITLE".to_string()).into()),
+        );
+        assert_nom(
+            " missing:date_taken",
+            parse_field_presence,
+            (
+                "",
+                EntrySearchQuery::from(EntriesWithField("DATE_TAKEN".to_string())).invert(),
+            ),
+        );
+    }
+
+    #[test]
+    pub fn parse_field_predicate_test() {
+        assert_nom(
+            " author:\"Jane Doe\" maxwell",
+            parse_field_predicate,
+            (" maxwell", author("Jane Doe", TextMatchMode::Contains)),
+        );
+        assert_nom(
+            "author:^jane",
+            parse_field_predicate,
+            ("", author("jane", TextMatchMode::StartsWith)),
+        );
+        assert_nom(
+            "author:=bob)",
+            parse_field_predicate,
+            (")", author("bob", TextMatchMode::Equals)),
+        );
+
+        let date = |year, month, day| {
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+        assert_nom(
+            "date_taken:>2020-01-01",
+            parse_field_predicate,
+            (
+                "",
+                DatetimeFieldRange::after("DATE_TAKEN", date(2020, 1, 1)).into(),
+            ),
+        );
+        assert_nom(
+            "date_taken:<\"2020-01-01 10:00:00\"",
+            parse_field_predicate,
+            (
+                "",
+                DatetimeFieldRange::before(
+                    "DATE_TAKEN",
+                    date(2020, 1, 1) + chrono::Duration::hours(10),
+                )
+                .into(),
+            ),
+        );
+        assert_nom(
+            "date_taken:>=2020-01-01",
+            parse_field_predicate,
+            (
+                "",
+                DatetimeFieldRange::at_or_after("DATE_TAKEN", date(2020, 1, 1)).into(),
+            ),
+        );
+        assert_nom(
+            "date_taken:<=2020-01-01",
+            parse_field_predicate,
+            (
+                "",
+                DatetimeFieldRange::at_or_before("DATE_TAKEN", date(2020, 1, 1)).into(),
+            ),
+        );
+        assert_nom(
+            "date_taken:2019-01-01..2020-01-01",
+            parse_field_predicate,
+            (
+                "",
+                DatetimeFieldRange::between("DATE_TAKEN", date(2019, 1, 1), date(2020, 1, 1))
+                    .into(),
+            ),
+        );
+        assert_nom(
+            "favorite:true",
+            parse_field_predicate,
+            (
+                "",
+                EntrySearchQuery::from(TextFieldMatch {
+                    field_type: "FAVORITE".to_string(),
+                    value: "true".to_string(),
+                    mode: TextMatchMode::Contains,
+                })
+                .or(EqEntryField {
+                    field_type: "FAVORITE".to_string(),
+                    value: FieldValue::Boolean(true),
+                }
+                .into()),
+            ),
+        );
+
+        // Once the field is found, the value has to be valid
+        assert!(matches!(
+            parse_field_predicate::<VerboseError<_>>("date_taken:>yesterday"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            parse_field_predicate::<VerboseError<_>>("maxwell"),
+            Err(nom::Err::Error(_))
+        ));
+    }
+
+    #[test]
+    pub fn parse_tag_id_like_token_test() {
+        let tag_id_field = |value: &str| {
+            EntrySearchQuery::from(TextFieldMatch {
+                field_type: "TAG_ID".to_string(),
+                value: value.to_string(),
+                mode: TextMatchMode::Contains,
+            })
+        };
+
+        // Only the lowercase prefix with a number is a tag id
+        assert_nom(
+            "tag_id:5",
+            parse_filter_token,
+            (
+                "",
+                TagSearchQuery::from(EqTagId(5)).into_entry_search_query(),
+            ),
+        );
+        assert_nom("TAG_ID:5", parse_filter_token, ("", tag_id_field("5")));
+        assert_nom("tag_id:abc", parse_filter_token, ("", tag_id_field("abc")));
+        assert!(matches!(
+            parse_filter_token::<VerboseError<_>>("tag_id:>abc"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn field_search_test() {
+        let lib = get_test_library_with_fields().await;
+        let search = |input: &str| EntrySearchQuery::parse(input).unwrap();
+
+        assert_eq_entries_in(
+            &lib,
+            search("author:\"Jane\""),
+            vec!["maxwell.png", "doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(&lib, search("date_taken:>2020-01-01"), vec!["doge.png"]).await;
+        // `>` and `<` exclude their bound, `>=` and `<=` include it
+        assert_eq_entries_in(&lib, search("date_taken:>\"2021-02-03 12:00:00\""), vec![]).await;
+        assert_eq_entries_in(
+            &lib,
+            search("date_taken:>=\"2021-02-03 12:00:00\""),
+            vec!["doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            search("date_taken:<\"2021-02-03 12:00:00\""),
+            vec!["maxwell.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            search("date_taken:<=\"2021-02-03 12:00:00\""),
+            vec!["maxwell.png", "doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(&lib, search("date_taken:2019-06-01"), vec!["maxwell.png"]).await;
+        assert_eq_entries_in(&lib, search("favorite:true"), vec!["maxwell.png"]).await;
+        assert_eq_entries_in(
+            &lib,
+            search("has:title or author:^\"jane s\""),
+            vec!["doge_and_maxwell.png", "doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            search("missing:author"),
+            vec!["OIIA.png", "somwhere/far/away.png"],
+        )
+        .await;
+    }
+}
@@ -11,6 +11,7 @@ use nom_language::error::VerboseError;
 
 pub mod and;
 pub mod expression;
+pub mod field;
 pub mod not;
 pub mod or;
 pub mod tag_id;
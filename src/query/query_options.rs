@@ -0,0 +1,288 @@
+use snafu::ResultExt as _;
+
+use crate::Entry;
+use crate::models::datetime_field::datetime_value_sql;
+use crate::models::errors::sqlx_error::SqlxError;
+use crate::models::errors::sqlx_error::SqlxSnafu;
+
+/// The column used to sort entries
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum EntrySortField {
+    Path,
+    Filename,
+    DateAdded,
+    DateModified,
+    #[default]
+    Id,
+
+    /// A shuffled order. The same seed always gives the same order, so it can be paginated
+    Random(u32),
+
+    /// The earliest value of the datetime fields of this type (Ex: `DATE_TAKEN`).
+    ///
+    /// Entries without such field, or with a value that can't be parsed, are sorted first in ascending order
+    DatetimeField(String),
+}
+
+impl EntrySortField {
+    /// Get the SQL expression to sort on
+    pub fn as_sql(&self) -> String {
+        match self {
+            Self::Path => "`entries`.`path`".to_string(),
+            Self::Filename => "`entries`.`filename`".to_string(),
+            // Entries without dates are sorted first, and can still be paginated
+            Self::DateAdded => "COALESCE(`entries`.`date_added`, '')".to_string(),
+            Self::DateModified => "COALESCE(`entries`.`date_modified`, '')".to_string(),
+            Self::Id => "`entries`.`id`".to_string(),
+            // The seed is mixed in before the multiplication, and the result squared, so each seed gives its own order
+            Self::Random(seed) => {
+                let mixed = format!("((`entries`.`id` + {seed}) * 1103515245 + 12345) % 2147483647");
+                format!("(({mixed}) * ({mixed}) % 2147483647)")
+            }
+            // The value is normalized, as older versions of the app wrote other formats
+            Self::DatetimeField(type_key) => format!(
+                "COALESCE((
+                    SELECT MIN({})
+                    FROM `datetime_fields`
+                    WHERE `datetime_fields`.`entry_id` = `entries`.`id` AND `datetime_fields`.`type_key` = '{}'
+                ), '')",
+                datetime_value_sql("`datetime_fields`.`value`"),
+                type_key.replace('\'', "''")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Ascending => "ASC",
+            Self::Descending => "DESC",
+        }
+    }
+
+    /// The comparison operator that select the rows after a cursor
+    fn after_operator(&self) -> &'static str {
+        match self {
+            Self::Ascending => ">",
+            Self::Descending => "<",
+        }
+    }
+}
+
+/// Sorting and pagination options for [QueryEntryFilter::fetch_page](crate::query::trait_entry_filter::QueryEntryFilter::fetch_page)
+///
+/// Ties are broken by the entry id, so the order is always stable.
+///
+/// There are two ways to paginate:
+/// - `limit` and `offset`, which are simple but get slower the further the page is
+/// - `limit` and `after`, which continues after the last entry of the previous page (Keyset pagination).
+///   This stays fast on large libraries, and entries added or removed meanwhile don't shift the pages.
+///   The `after` entry must still exist in the database, or [sqlx::Error::RowNotFound] is returned
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EntryQueryOptions {
+    pub sort: EntrySortField,
+    pub direction: SortDirection,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+
+    /// The id of the entry to continue after
+    pub after: Option<i64>,
+}
+
+impl EntryQueryOptions {
+    pub fn sort_by(mut self, sort: EntrySortField, direction: SortDirection) -> Self {
+        self.sort = sort;
+        self.direction = direction;
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Continue after this entry
+    pub fn after(mut self, entry: &Entry) -> Self {
+        self.after = Some(entry.id);
+        self
+    }
+
+    /// Get the options for the page following `page`. Returns `None` if it was the last one
+    pub fn next_page(&self, page: &[Entry]) -> Option<Self> {
+        let last = page.last()?;
+        if self.limit.is_some_and(|limit| (page.len() as u64) < limit) {
+            return None;
+        }
+
+        Some(
+            Self {
+                offset: None,
+                ..self.clone()
+            }
+            .after(last),
+        )
+    }
+
+    /// Get the keyset condition. It uses the id of the `after` entry as bind parameter
+    pub fn get_after_condition(&self, bind_id: &mut u64) -> Option<String> {
+        self.after?;
+
+        let id = *bind_id;
+        *bind_id += 1;
+        let expr = self.sort.as_sql();
+        let operator = self.direction.after_operator();
+
+        Some(format!(
+            "({expr}, `entries`.`id`) {operator} ((SELECT {expr} FROM `entries` WHERE `entries`.`id` = ${id}), ${id})"
+        ))
+    }
+
+    /// Check that the `after` entry still exists, as the page would be silently empty otherwise
+    pub(crate) async fn check_after(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> Result<(), SqlxError> {
+        let Some(after) = self.after else {
+            return Ok(());
+        };
+
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM `entries` WHERE `id` = $1)")
+                .bind(after)
+                .fetch_one(conn)
+                .await
+                .context(SqlxSnafu)?;
+
+        if !exists {
+            return Err(sqlx::Error::RowNotFound).context(SqlxSnafu);
+        }
+
+        Ok(())
+    }
+
+    /// Get the `ORDER BY`, `LIMIT` and `OFFSET` clauses
+    pub fn get_order_clauses(&self) -> String {
+        let direction = self.direction.as_sql();
+        let mut sql = format!(
+            "ORDER BY {} {direction}, `entries`.`id` {direction}",
+            self.sort.as_sql()
+        );
+
+        // Sqlite needs a limit to have an offset
+        match (self.limit, self.offset) {
+            (Some(limit), Some(offset)) => sql.push_str(&format!(" LIMIT {limit} OFFSET {offset}")),
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {limit}")),
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {offset}")),
+            (None, None) => {}
+        }
+
+        sql
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use itertools::Itertools as _;
+
+    use crate::Entry;
+    use crate::models::entry::delete::FileDeletion;
+    use crate::query::entry_search_query::EntrySearchQuery;
+    use crate::query::eq_entry_name::EqEntryName;
+    use crate::query::query_options::EntryQueryOptions;
+    use crate::query::query_options::EntrySortField;
+    use crate::query::query_options::SortDirection;
+    use crate::query::trait_entry_filter::QueryEntryFilter as _;
+    use crate::tests::fixtures::data::get_test_library;
+
+    #[tokio::test]
+    pub async fn pagination_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+        let query = EntrySearchQuery::from(EqEntryName("doge.png".to_string())).invert();
+
+        let options = EntryQueryOptions::default()
+            .sort_by(EntrySortField::Filename, SortDirection::Descending)
+            .limit(3);
+
+        // Keyset pagination
+        let mut pages = Vec::new();
+        let mut next = Some(options.clone());
+        while let Some(options) = next {
+            let page = query.fetch_page(conn, &options).await.unwrap();
+            next = options.next_page(&page);
+            pages.push(page.into_iter().map(|entry| entry.filename).collect_vec());
+        }
+
+        assert_eq!(
+            pages,
+            vec![
+                vec!["maxwell.png", "doge_and_maxwell.png", "away.png"],
+                vec!["OIIA.png"],
+            ]
+        );
+
+        // Offset pagination
+        let page = query
+            .fetch_page(conn, &options.offset(1))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect_vec();
+        assert_eq!(page, vec!["doge_and_maxwell.png", "away.png", "OIIA.png"]);
+
+        // A shuffled order is the same for the same seed
+        let shuffled = EntryQueryOptions::default()
+            .sort_by(EntrySortField::Random(42), SortDirection::Ascending);
+        let first = query.fetch_page(conn, &shuffled).await.unwrap();
+        assert_eq!(first, query.fetch_page(conn, &shuffled).await.unwrap());
+        assert_eq!(first.len(), 4);
+
+        // But another seed gives another order, not only the same one rotated
+        let ids = |entries: Vec<Entry>| entries.into_iter().map(|entry| entry.id).collect_vec();
+        let other = EntryQueryOptions::default()
+            .sort_by(EntrySortField::Random(7), SortDirection::Ascending);
+        assert_eq!(ids(first), vec![1, 4, 5, 3]);
+        assert_eq!(
+            ids(query.fetch_page(conn, &other).await.unwrap()),
+            vec![4, 3, 1, 5]
+        );
+    }
+
+    #[tokio::test]
+    pub async fn deleted_after_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+        let query = EntrySearchQuery::from(EqEntryName("doge.png".to_string())).invert();
+
+        let page = EntryQueryOptions::default().limit(2);
+        let entries = query.fetch_page(conn, &page).await.unwrap();
+        let next = page.next_page(&entries).unwrap();
+
+        // The cursor entry got deleted between the pages
+        entries[1]
+            .clone()
+            .delete_cascade(conn, &lib.path, FileDeletion::Keep)
+            .await
+            .unwrap();
+
+        let err = query.fetch_page(conn, &next).await.unwrap_err();
+        assert!(matches!(
+            std::error::Error::source(&err).and_then(|source| source.downcast_ref::<sqlx::Error>()),
+            Some(sqlx::Error::RowNotFound)
+        ));
+    }
+}
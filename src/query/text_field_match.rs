@@ -0,0 +1,106 @@
+use core::ops::AddAssign as _;
+
+use crate::query::SQLQuery;
+use crate::query::entry_search_query::EntrySearchQuery;
+use crate::query::trait_entry_filter::QueryEntryFilter;
+
+/// How a [TextFieldMatch] compares the value of the fields. All the modes ignore the case.
+///
+/// The case is folded by SQLite's `LOWER`, which only knows the ASCII letters. So `é` and `É` are still different
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TextMatchMode {
+    #[default]
+    Contains,
+    StartsWith,
+    Equals,
+}
+
+/// Entries with a text field of the type matching the value. Empty fields never match
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextFieldMatch {
+    pub field_type: String,
+    pub value: String,
+    pub mode: TextMatchMode,
+}
+
+impl QueryEntryFilter for TextFieldMatch {
+    fn get_where_condition(&self, bind_id: &mut u64) -> Option<String> {
+        let type_id = *bind_id;
+        bind_id.add_assign(1);
+        let value_id = *bind_id;
+        bind_id.add_assign(1);
+
+        let condition = match self.mode {
+            TextMatchMode::Contains => format!("INSTR(LOWER(`value`), LOWER(${value_id})) > 0"),
+            TextMatchMode::StartsWith => {
+                format!("SUBSTR(LOWER(`value`), 1, LENGTH(${value_id})) = LOWER(${value_id})")
+            }
+            TextMatchMode::Equals => format!("LOWER(`value`) = LOWER(${value_id})"),
+        };
+
+        Some(format!(
+            "`entries`.`id` IN (
+                    SELECT `entry_id`
+                    FROM `text_fields`
+                    WHERE `type_key` = ${type_id} AND {condition}
+                )"
+        ))
+    }
+
+    fn bind<'q, O>(&'q self, query: SQLQuery<'q, O>) -> SQLQuery<'q, O> {
+        query.bind(&self.field_type).bind(&self.value)
+    }
+}
+
+impl From<TextFieldMatch> for EntrySearchQuery {
+    fn from(value: TextFieldMatch) -> Self {
+        EntrySearchQuery::TextFieldMatch(value)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::query::text_field_match::TextFieldMatch;
+    use crate::query::text_field_match::TextMatchMode;
+    use crate::tests::fixtures::assertions::assert_eq_entries_in;
+    use crate::tests::fixtures::data::get_test_library_with_fields;
+
+    fn author(value: &str, mode: TextMatchMode) -> TextFieldMatch {
+        TextFieldMatch {
+            field_type: "AUTHOR".to_string(),
+            value: value.to_string(),
+            mode,
+        }
+    }
+
+    #[tokio::test]
+    pub async fn text_field_match_test() {
+        let lib = get_test_library_with_fields().await;
+
+        assert_eq_entries_in(
+            &lib,
+            author("JANE", TextMatchMode::Contains),
+            vec!["maxwell.png", "doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            author("doe", TextMatchMode::Contains),
+            vec!["maxwell.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            author("jane s", TextMatchMode::StartsWith),
+            vec!["doge.png"],
+        )
+        .await;
+        assert_eq_entries_in(
+            &lib,
+            author("bob", TextMatchMode::Equals),
+            vec!["doge_and_maxwell.png"],
+        )
+        .await;
+        assert_eq_entries_in(&lib, author("bo", TextMatchMode::Equals), vec![]).await;
+    }
+}
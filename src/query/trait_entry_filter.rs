@@ -1,12 +1,21 @@
 use core::ops::Deref as _;
 
-use snafu::ResultExt;
-use sqlx::AssertSqlSafe;
+use futures::StreamExt as _;
+use futures::TryFutureExt as _;
+use futures::stream::BoxStream;
 
 use crate::Entry;
 use crate::models::errors::sqlx_error::SqlxError;
-use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
 use crate::query::SQLQuery;
+use crate::query::count_rows;
+use crate::query::fetch_all_rows;
+use crate::query::fetch_optional_row;
+use crate::query::fetch_row_ids;
+use crate::query::query_options::EntryQueryOptions;
+use crate::query::rows_exist;
+use crate::query::select_rows_sql;
+use crate::query::stream_rows;
 
 /// Trait for all the query fragments that can generate `WHERE` filter for a `SELECT` on the `entries` table
 pub trait QueryEntryFilter {
@@ -29,12 +38,49 @@ pub trait QueryEntryFilter {
     where
         Self: Sync,
     {
-        async {
-            let sql = self
-                .as_entry_select(&mut 1)
-                .unwrap_or_else(|| "SELECT * FROM `entries`".to_string());
-            let query = sqlx::query_as(AssertSqlSafe(sql));
-            self.bind(query).fetch_all(conn).await.context(SqlxSnafu)
+        async move {
+            let sql = select_entries_sql(conn, self.get_where_condition(&mut 1)).await?;
+            fetch_all_rows(conn, sql, |query| self.bind(query)).await
+        }
+    }
+
+    /// Fetch the matching entries, sorted and paginated
+    fn fetch_page(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+        options: &EntryQueryOptions,
+    ) -> impl std::future::Future<Output = Result<Vec<Entry>, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            options.check_after(conn).await?;
+
+            let mut bind_id = 1;
+            let conditions = [
+                self.get_where_condition(&mut bind_id),
+                options.get_after_condition(&mut bind_id),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|cond| format!("({cond})"))
+            .collect::<Vec<_>>();
+            let condition = (!conditions.is_empty()).then(|| conditions.join(" AND "));
+
+            let sql = format!(
+                "{} {}",
+                select_entries_sql(conn, condition).await?,
+                options.get_order_clauses()
+            );
+
+            fetch_all_rows(conn, sql, |query| {
+                let mut query = self.bind(query);
+                if let Some(after) = options.after {
+                    query = query.bind(after);
+                }
+                query
+            })
+            .await
         }
     }
 
@@ -45,19 +91,7 @@ pub trait QueryEntryFilter {
     where
         Self: Sync,
     {
-        async {
-            let sql = self
-                .as_entry_select(&mut 1)
-                .map(|sql| format!("{sql} LIMIT 1"))
-                .unwrap_or_else(|| "SELECT * FROM `entries` LIMIT 1".to_string());
-
-            println!("{sql}");
-            let query = sqlx::query_as(AssertSqlSafe(sql));
-            self.bind(query)
-                .fetch_optional(conn)
-                .await
-                .context(SqlxSnafu)
-        }
+        self.fetch_optional(conn)
     }
 
     fn fetch_optional(
@@ -67,17 +101,80 @@ pub trait QueryEntryFilter {
     where
         Self: Sync,
     {
-        async {
-            let sql = self
-                .as_entry_select(&mut 1)
-                .unwrap_or_else(|| "SELECT * FROM `entries`".to_string());
-            let query = sqlx::query_as(AssertSqlSafe(sql));
-            self.bind(query)
-                .fetch_optional(conn)
-                .await
-                .context(SqlxSnafu)
+        async move {
+            let sql = select_entries_sql(conn, self.get_where_condition(&mut 1)).await?;
+            fetch_optional_row(conn, sql, |query| self.bind(query)).await
         }
     }
+
+    /// Stream the matching rows instead of loading them all at once
+    fn stream<'l>(
+        &'l self,
+        conn: &'l mut sqlx::SqliteConnection,
+    ) -> BoxStream<'l, Result<Entry, SqlxError>>
+    where
+        Self: Sync,
+    {
+        async move {
+            let sql = select_entries_sql(conn, self.get_where_condition(&mut 1)).await?;
+            Ok::<_, SqlxError>(stream_rows(conn, sql, |query| self.bind(query)))
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+
+    /// Fetch the ids of the matching rows
+    fn fetch_ids(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<Vec<i64>, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        fetch_row_ids(conn, "entries", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+
+    /// Count the matching rows
+    fn count(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<i64, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        count_rows(conn, "entries", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+
+    /// Return true if at least one row matches
+    fn exists(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<bool, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        rows_exist(conn, "entries", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+}
+
+/// Select the whole rows of `entries`, with the columns of the connection's schema
+async fn select_entries_sql(
+    conn: &mut sqlx::SqliteConnection,
+    condition: Option<String>,
+) -> Result<String, SqlxError> {
+    select_rows_sql(
+        conn,
+        "entries",
+        SchemaCapabilities::entry_select_columns,
+        condition,
+    )
+    .await
 }
 
 impl<T> QueryEntryFilter for Box<T>
@@ -92,3 +189,58 @@ where
         self.deref().get_where_condition(bind_id)
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use futures::TryStreamExt as _;
+
+    use crate::query::eq_entry_name::EqEntryName;
+    use crate::query::eq_tag_string::EqTagString;
+    use crate::query::trait_entry_filter::QueryEntryFilter as _;
+    use crate::query::trait_tag_filter::TagFilter as _;
+    use crate::tests::fixtures::data::get_test_library;
+
+    #[tokio::test]
+    pub async fn terminal_operations_test() {
+        let lib = get_test_library().await;
+        let conn = &mut *lib.db.get().await.unwrap();
+        let maxwell = EqTagString("maxwell".to_string());
+        assert_eq!(maxwell.count(conn).await.unwrap(), 1);
+
+        let tag_ids = maxwell
+            .fetch_all(conn)
+            .await
+            .unwrap()
+            .iter()
+            .map(|tag| tag.id)
+            .collect::<Vec<_>>();
+        assert_eq!(tag_ids.len(), 1);
+        assert_eq!(maxwell.fetch_ids(conn).await.unwrap(), tag_ids);
+        assert_eq!(
+            maxwell.fetch_one(conn).await.unwrap().map(|tag| tag.id),
+            Some(tag_ids[0])
+        );
+        assert!(maxwell.exists(conn).await.unwrap());
+        let streamed: Vec<_> = maxwell.stream(conn).try_collect().await.unwrap();
+        assert_eq!(
+            streamed.iter().map(|tag| tag.id).collect::<Vec<_>>(),
+            tag_ids
+        );
+
+        let query = maxwell.into_entry_filter();
+        assert_eq!(query.count(conn).await.unwrap(), 2);
+        assert!(query.exists(conn).await.unwrap());
+
+        let mut ids = query.fetch_ids(conn).await.unwrap();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3]);
+
+        let streamed: Vec<_> = query.stream(conn).try_collect().await.unwrap();
+        assert_eq!(streamed.len(), 2);
+
+        let missing = EqEntryName("grumpy.png".to_string());
+        assert_eq!(missing.count(conn).await.unwrap(), 0);
+        assert!(!missing.exists(conn).await.unwrap());
+        assert!(missing.fetch_optional(conn).await.unwrap().is_none());
+    }
+}
@@ -1,14 +1,22 @@
 use core::ops::Deref;
 
-use snafu::ResultExt as _;
-use sqlx::AssertSqlSafe;
+use futures::StreamExt as _;
+use futures::TryFutureExt as _;
+use futures::stream::BoxStream;
 
 use crate::Tag;
 use crate::models::errors::sqlx_error::SqlxError;
-use crate::models::errors::sqlx_error::SqlxSnafu;
+use crate::models::library::capabilities::SchemaCapabilities;
 use crate::query::SQLQuery;
+use crate::query::count_rows;
 use crate::query::entries_with_tags::EntriesWithTags;
 use crate::query::eq_tag_or_children::EqTagOrChildren;
+use crate::query::fetch_all_rows;
+use crate::query::fetch_optional_row;
+use crate::query::fetch_row_ids;
+use crate::query::rows_exist;
+use crate::query::select_rows_sql;
+use crate::query::stream_rows;
 
 /// Trait for all the querry fragments that can generate `WHERE` filter for a `SELECT` on the `tags` table
 pub trait TagFilter {
@@ -31,15 +39,22 @@ pub trait TagFilter {
     where
         Self: Sync,
     {
-        async {
-            let sql = self
-                .as_tag_select(&mut 1)
-                .unwrap_or_else(|| "SELECT * FROM `tags`".to_string());
-            let query = sqlx::query_as(AssertSqlSafe(sql));
-            self.bind(query).fetch_all(conn).await.context(SqlxSnafu)
+        async move {
+            let sql = select_tags_sql(conn, self.get_where_condition(&mut 1)).await?;
+            fetch_all_rows(conn, sql, |query| self.bind(query)).await
         }
     }
 
+    fn fetch_one(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<Option<Tag>, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        self.fetch_optional(conn)
+    }
+
     fn fetch_optional(
         &self,
         conn: &mut sqlx::SqliteConnection,
@@ -47,18 +62,67 @@ pub trait TagFilter {
     where
         Self: Sync,
     {
-        async {
-            let sql = self
-                .as_tag_select(&mut 1)
-                .unwrap_or_else(|| "SELECT * FROM `tags`".to_string());
-            let query = sqlx::query_as(AssertSqlSafe(sql));
-            self.bind(query)
-                .fetch_optional(conn)
-                .await
-                .context(SqlxSnafu)
+        async move {
+            let sql = select_tags_sql(conn, self.get_where_condition(&mut 1)).await?;
+            fetch_optional_row(conn, sql, |query| self.bind(query)).await
         }
     }
 
+    /// Stream the matching rows instead of loading them all at once
+    fn stream<'l>(
+        &'l self,
+        conn: &'l mut sqlx::SqliteConnection,
+    ) -> BoxStream<'l, Result<Tag, SqlxError>>
+    where
+        Self: Sync,
+    {
+        async move {
+            let sql = select_tags_sql(conn, self.get_where_condition(&mut 1)).await?;
+            Ok::<_, SqlxError>(stream_rows(conn, sql, |query| self.bind(query)))
+        }
+        .try_flatten_stream()
+        .boxed()
+    }
+
+    /// Fetch the ids of the matching rows
+    fn fetch_ids(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<Vec<i64>, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        fetch_row_ids(conn, "tags", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+
+    /// Count the matching rows
+    fn count(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<i64, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        count_rows(conn, "tags", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+
+    /// Return true if at least one row matches
+    fn exists(
+        &self,
+        conn: &mut sqlx::SqliteConnection,
+    ) -> impl std::future::Future<Output = Result<bool, SqlxError>> + Send
+    where
+        Self: Sync,
+    {
+        rows_exist(conn, "tags", self.get_where_condition(&mut 1), |query| {
+            self.bind(query)
+        })
+    }
+
     fn into_entry_filter(self) -> EntriesWithTags<Self>
     where
         Self: Sized,
@@ -74,6 +138,20 @@ pub trait TagFilter {
     }
 }
 
+/// Select the whole rows of `tags`, with the columns of the connection's schema
+async fn select_tags_sql(
+    conn: &mut sqlx::SqliteConnection,
+    condition: Option<String>,
+) -> Result<String, SqlxError> {
+    select_rows_sql(
+        conn,
+        "tags",
+        SchemaCapabilities::tag_select_columns,
+        condition,
+    )
+    .await
+}
+
 impl<T> TagFilter for Box<T>
 where
     T: TagFilter,
@@ -1,14 +1,21 @@
 use itertools::Itertools as _;
 
+use crate::Library;
 use crate::query::trait_entry_filter::QueryEntryFilter;
 use crate::tests::fixtures::data::get_test_library;
 
-pub async fn assert_eq_entries<T>(query: T, mut expected: Vec<&str>)
+pub async fn assert_eq_entries<T>(query: T, expected: Vec<&str>)
 where
     T: QueryEntryFilter + Sync,
 {
-    let lib = get_test_library().await;
+    assert_eq_entries_in(&get_test_library().await, query, expected).await;
+}
 
+/// Same as [assert_eq_entries], on a specific library
+pub async fn assert_eq_entries_in<T>(lib: &Library, query: T, mut expected: Vec<&str>)
+where
+    T: QueryEntryFilter + Sync,
+{
     // Run the query
 
     println!();
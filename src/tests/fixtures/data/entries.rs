@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use crate::Entry;
 use crate::Library;
 
@@ -10,16 +12,9 @@ pub(super) async fn add_test_entries(lib: &Library) {
 }
 
 async fn add_entry(lib: &Library, name: &str) {
-    Entry {
-        date_added: None,
-        date_created: None,
-        date_modified: None,
-        filename: name.split('/').next_back().unwrap().to_string(),
-        id: 0,
-        path: name.to_string(),
-        suffix: name.split('.').next_back().unwrap().to_string(),
-    }
-    .insert(&mut lib.db.get().await.unwrap())
-    .await
-    .unwrap();
+    Entry::from_path(lib, Path::new(name))
+        .unwrap()
+        .insert(&mut lib.db.get().await.unwrap())
+        .await
+        .unwrap();
 }
@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+
+use crate::Entry;
+use crate::Library;
+use crate::models::value_type::FieldKind;
+use crate::models::value_type::ValueType;
+
+pub(super) async fn add_test_fields(lib: &Library) {
+    let conn = &mut *lib.db.get().await.unwrap();
+    ValueType::create_custom(conn, "Favorite", FieldKind::Boolean)
+        .await
+        .unwrap();
+
+    let maxwell = get_entry(conn, "maxwell.png").await;
+    maxwell
+        .add_text_field(conn, "DESCRIPTION", Some("A very dingus cat"))
+        .await
+        .unwrap();
+    maxwell
+        .add_text_field(conn, "AUTHOR", Some("Jane Doe"))
+        .await
+        .unwrap();
+    maxwell
+        .add_datetime_field(conn, "DATE_TAKEN", Some(date(2019, 6, 1)))
+        .await
+        .unwrap();
+    maxwell
+        .add_boolean_field(conn, "FAVORITE", true)
+        .await
+        .unwrap();
+
+    let doge = get_entry(conn, "doge.png").await;
+    doge.add_text_field(conn, "AUTHOR", Some("jane smith"))
+        .await
+        .unwrap();
+    doge.add_datetime_field(conn, "DATE_TAKEN", Some(date(2021, 2, 3)))
+        .await
+        .unwrap();
+    doge.add_boolean_field(conn, "FAVORITE", false)
+        .await
+        .unwrap();
+
+    let doge_and_maxwell = get_entry(conn, "doge_and_maxwell.png").await;
+    doge_and_maxwell
+        .add_text_field(conn, "AUTHOR", Some("Bob"))
+        .await
+        .unwrap();
+    doge_and_maxwell
+        .add_text_field(conn, "TITLE", None)
+        .await
+        .unwrap();
+}
+
+async fn get_entry(conn: &mut sqlx::SqliteConnection, path: &str) -> Entry {
+    Entry::find_by_path(conn, path)
+        .await
+        .unwrap()
+        .first()
+        .cloned()
+        .unwrap()
+}
+
+fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
@@ -1,6 +1,7 @@
 mod tag_aliases;
 use crate::Library;
 use crate::tests::fixtures::data::entries::add_test_entries;
+use crate::tests::fixtures::data::fields::add_test_fields;
 use crate::tests::fixtures::data::tag_aliases::add_test_tag_aliases;
 use crate::tests::fixtures::data::tag_entries::add_test_tag_entries;
 use crate::tests::fixtures::data::tag_parents::add_test_tag_parents;
@@ -8,6 +9,7 @@ use crate::tests::fixtures::data::tags::add_test_tags;
 use crate::tests::fixtures::raw_library::get_empty_library;
 
 pub mod entries;
+pub mod fields;
 pub mod tag_entries;
 pub mod tag_parents;
 pub mod tags;
@@ -24,3 +26,12 @@ pub async fn get_test_library() -> Library {
 
     lib
 }
+
+/// Return an inmemmory database with testing data, and fields on some entries
+pub async fn get_test_library_with_fields() -> Library {
+    let lib = get_test_library().await;
+
+    add_test_fields(&lib).await;
+
+    lib
+}